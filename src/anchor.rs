@@ -1,5 +1,6 @@
 use yew::{prelude::*, html::Scope};
-use crate::dragndrop::{DragCallbacks, DragHandler, UnifiedPointerEvent};
+use js_sys::Date;
+use crate::dragndrop::{DragCallbacks, DragHandler, DragOptions, UnifiedPointerEvent};
 
 #[derive(Properties, PartialEq)]
 pub struct AnchorProps {
@@ -7,16 +8,51 @@ pub struct AnchorProps {
     #[prop_or_default] pub on_begin: Option<Callback<()>>,
     #[prop_or_default] pub on_move: Option<Callback<(i32, i32)>>,
     #[prop_or_default] pub on_end: Option<Callback<()>>,
+    /// Called instead of `on_end` when a drag is aborted
+    ///
+    /// Without it the anchor moves back to where the drag began and ends normally.
+    #[prop_or_default] pub on_cancel: Option<Callback<()>>,
+    /// Called instead of `on_begin`, `on_move` and `on_end` if the pointer barely moved
+    #[prop_or_default] pub on_click: Option<Callback<()>>,
+    /// Also triggered by pressing enter while the anchor is focused
+    #[prop_or_default] pub on_double_click: Option<Callback<()>>,
+    /// Name announced by screen readers
+    #[prop_or_default] pub label: Option<String>,
+    /// Whether the anchor separates content left and right of it instead of above and below
+    #[prop_or_default] pub vertical: bool,
+    /// Position of the anchor between `min` and `max` announced by screen readers
+    #[prop_or_default] pub value: Option<f64>,
+    #[prop_or(0.0)] pub min: f64,
+    #[prop_or(100.0)] pub max: f64,
+    /// Pixels the arrow keys move the anchor by
+    #[prop_or(10)] pub step: i32,
+    /// Pixels the arrow keys move the anchor by while shift is held
+    #[prop_or(50)] pub large_step: i32,
+    /// Only move an outline while dragging and emit a single `on_move` when released
+    #[prop_or_default] pub ghost: bool,
 }
 pub struct Anchor {
+    start_x: i32,
+    start_y: i32,
     last_x: i32,
     last_y: i32,
+    dragging: bool,
+    last_click: f64,
     drag_callbacks: DragCallbacks,
 }
+
+/// Distance in pixels a pointer may move while still counting as click
+const CLICK_DISTANCE: i32 = 3;
+/// Time in milliseconds a finger has to rest on an anchor to drag it without moving
+const LONG_PRESS_TIME: u32 = 500;
+/// Time in milliseconds two clicks may be apart to count as double click
+const DOUBLE_CLICK_TIME: f64 = 400.0;
 pub enum AnchorMsg {
     Down(i32, i32),
     Move(i32, i32),
     Up,
+    Cancel,
+    Click,
 }
 
 impl Component for Anchor {
@@ -25,8 +61,12 @@ impl Component for Anchor {
 
     fn create(ctx: &Context<Self>) -> Self {
         Anchor {
+            start_x: 0,
+            start_y: 0,
             last_x: 0,
             last_y: 0,
+            dragging: false,
+            last_click: 0.0,
             drag_callbacks: AnchorDragHandler(ctx.link().clone()).into_callbacks(),
         }
     }
@@ -34,8 +74,11 @@ impl Component for Anchor {
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             AnchorMsg::Down(x, y) => {
+                self.start_x = x;
+                self.start_y = y;
                 self.last_x = x;
                 self.last_y = y;
+                self.dragging = true;
                 if let Some(callback) = ctx.props().on_begin.as_ref() {
                     callback.emit(());
                 }
@@ -45,27 +88,111 @@ impl Component for Anchor {
                 let dy = y - self.last_y;
                 self.last_x = x;
                 self.last_y = y;
+                if ctx.props().ghost {
+                    return true;
+                }
                 if let Some(callback) = ctx.props().on_move.as_ref() {
                     callback.emit((dx, dy));
                 }
 
             },
             AnchorMsg::Up => {
-                if let Some(callback) = ctx.props().on_end.as_ref() {
+                let props = ctx.props();
+                self.dragging = false;
+                if props.ghost {
+                    if let Some(callback) = props.on_move.as_ref() {
+                        callback.emit((self.last_x - self.start_x, self.last_y - self.start_y));
+                    }
+                }
+                if let Some(callback) = props.on_end.as_ref() {
+                    callback.emit(());
+                }
+            },
+            AnchorMsg::Click => {
+                let props = ctx.props();
+                let now = Date::now();
+                if now - self.last_click <= DOUBLE_CLICK_TIME {
+                    self.last_click = 0.0;
+                    if let Some(callback) = props.on_double_click.as_ref() {
+                        callback.emit(());
+                    }
+                } else {
+                    self.last_click = now;
+                    if let Some(callback) = props.on_click.as_ref() {
+                        callback.emit(());
+                    }
+                }
+            },
+            AnchorMsg::Cancel => {
+                let props = ctx.props();
+                self.dragging = false;
+                if let Some(callback) = props.on_cancel.as_ref() {
                     callback.emit(());
+                } else {
+                    if let (Some(callback), false) = (props.on_move.as_ref(), props.ghost) {
+                        callback.emit((self.start_x - self.last_x, self.start_y - self.last_y));
+                    }
+                    if let Some(callback) = props.on_end.as_ref() {
+                        callback.emit(());
+                    }
                 }
+                self.last_x = self.start_x;
+                self.last_y = self.start_y;
             },
         }
         true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let on_double_click = props.on_double_click.clone();
+        let on_move = props.on_move.clone();
+        let (step, large_step) = (props.step, props.large_step);
+        let (onpointerdown, onmousedown, ontouchstart) = self.drag_callbacks.listeners();
         return html!{
             <div
-                class={ctx.props().class.clone()}
-                onmousedown={self.drag_callbacks.mouse.clone()}
-                ontouchstart={self.drag_callbacks.touch.clone()}
-            />
+                class={props.class.clone()}
+                role="separator"
+                aria-label={props.label.clone()}
+                aria-orientation={if props.vertical { "vertical" } else { "horizontal" }}
+                aria-valuenow={props.value.map(|value| value.round().to_string())}
+                aria-valuemin={props.value.map(|_| props.min.round().to_string())}
+                aria-valuemax={props.value.map(|_| props.max.round().to_string())}
+                tabindex="0"
+                onpointerdown={onpointerdown}
+                onmousedown={onmousedown}
+                ontouchstart={ontouchstart}
+                onkeydown={Callback::from(move |event: KeyboardEvent| {
+                    // Leave combinations to the screen's shortcuts and the browser
+                    if event.ctrl_key() || event.alt_key() || event.meta_key() {
+                        return;
+                    }
+                    let step = if event.shift_key() { large_step } else { step };
+                    let delta = match event.key().as_str() {
+                        "ArrowUp"    => (0, -step),
+                        "ArrowLeft"  => (-step, 0),
+                        "ArrowDown"  => (0, step),
+                        "ArrowRight" => (step, 0),
+                        "Enter" => {
+                            if let Some(callback) = on_double_click.as_ref() {
+                                event.prevent_default();
+                                callback.emit(());
+                            }
+                            return;
+                        }
+                        _ => return,
+                    };
+                    if let Some(callback) = on_move.as_ref() {
+                        event.prevent_default();
+                        callback.emit(delta);
+                    }
+                })}
+            >
+                if props.ghost && self.dragging {
+                    <div class="waw-ghost" style={format!("--dx: {}px; --dy: {}px",
+                        self.last_x - self.start_x, self.last_y - self.start_y)}/>
+                }
+            </div>
         };
     }
 }
@@ -83,4 +210,19 @@ impl DragHandler for AnchorDragHandler {
     fn on_up(&mut self, _event: &UnifiedPointerEvent) {
         self.0.send_message(AnchorMsg::Up);
     }
+
+    fn on_cancel(&mut self) {
+        self.0.send_message(AnchorMsg::Cancel);
+    }
+
+    fn on_click(&mut self, _event: &UnifiedPointerEvent) {
+        self.0.send_message(AnchorMsg::Click);
+    }
+
+    fn options(&self) -> DragOptions {
+        DragOptions {
+            threshold: CLICK_DISTANCE,
+            long_press: Some(LONG_PRESS_TIME),
+        }
+    }
 }
\ No newline at end of file
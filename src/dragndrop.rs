@@ -1,136 +1,369 @@
 /**
  * HTML's drag and drop api worked fine,
  * but it is not mobile friendly.
+ *
+ * Pointer events cover mouse, pen and touch with a single code path,
+ * mouse and touch events are only a fallback for browsers lacking them.
  */
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::rc::{Rc, Weak};
-use std::sync::Mutex;
 use wasm_bindgen::JsCast;
-use web_sys::{Event, EventTarget, PointerEvent, MouseEvent, Touch, TouchEvent};
+use web_sys::{Element, Event, EventTarget, KeyboardEvent, PointerEvent, MouseEvent, Touch, TouchEvent};
+use js_sys::Reflect;
 use gloo::utils::window;
 use gloo::events::{EventListener, EventListenerOptions};
+use gloo::timers::callback::Timeout;
+use gloo::render::{request_animation_frame, AnimationFrame};
 use yew::prelude::*;
 
+/// When a pressed pointer starts dragging
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DragOptions {
+    /// Distance in pixels the pointer has to move before the drag begins
+    pub threshold: i32,
+    /// Milliseconds after which a touch begins the drag even without moving
+    pub long_press: Option<u32>,
+}
+
 pub trait DragHandler: Sized + 'static {
+    /// Called once the drag begins with the event which pressed the pointer
     fn on_down(&mut self, event: &UnifiedPointerEvent);
+    /// Called at most once per animation frame with the latest position
     fn on_move(&mut self, event: &UnifiedPointerEvent);
     fn on_up(&mut self, event: &UnifiedPointerEvent);
 
+    /// Called instead of `on_up` when the drag is aborted
+    ///
+    /// This happens when the browser takes over the gesture,
+    /// the page loses focus or the user presses escape.
+    fn on_cancel(&mut self) {}
+
+    /// Called instead of any other method when the pointer is released before the drag began
+    ///
+    /// This is a click or, if `event.pointer_type()` is `"touch"`, a tap.
+    fn on_click(&mut self, _event: &UnifiedPointerEvent) {}
+
+    fn options(&self) -> DragOptions {
+        DragOptions::default()
+    }
+
     fn into_callbacks(self) -> DragCallbacks {
-        let outer_drag = Rc::new(Mutex::new(InnerDragHandler {
-            listener: None,
-            handler: self,
-            self_ref: Weak::new(),
-        }));
-        let mut drag = outer_drag.lock()
-            .expect("Nobody else can own this yet.");
-        drag.self_ref = Rc::downgrade(&outer_drag);
+        let drag = Rc::new_cyclic(|self_ref| SharedDrag {
+            inner: RefCell::new(InnerDragHandler {
+                listeners: Vec::new(),
+                pointer: None,
+                pending: None,
+                long_press: None,
+                queued_move: None,
+                handler: self,
+                self_ref: self_ref.clone(),
+            }),
+            queue: RefCell::new(VecDeque::new()),
+        });
+        let down = |drag: &Rc<SharedDrag<Self>>| {
+            let drag = Rc::downgrade(drag);
+            move |event: &Event| {
+                if let Some(drag) = drag.upgrade() {
+                    drag.dispatch(DragStep::Down(event.clone()));
+                }
+            }
+        };
 
         DragCallbacks {
             mouse: {
-                let handler = drag.get_down_handler();
-                Callback::from(move |event: MouseEvent| handler(&event.dyn_ref().unwrap()))
+                let handler = down(&drag);
+                Callback::from(move |event: MouseEvent| handler(event.dyn_ref().unwrap()))
             },
             pointer: {
-                let handler = drag.get_down_handler();
-                Callback::from(move |event: PointerEvent| handler(&event.dyn_ref().unwrap()))
+                let handler = down(&drag);
+                Callback::from(move |event: PointerEvent| handler(event.dyn_ref().unwrap()))
             },
             touch: {
-                let handler = drag.get_down_handler();
-                Callback::from(move |event: TouchEvent| handler(&event.dyn_ref().unwrap()))
+                let handler = down(&drag);
+                Callback::from(move |event: TouchEvent| handler(event.dyn_ref().unwrap()))
             },
+            _drag: drag,
         }
     }
 }
 
+/**
+ * Callbacks starting a drag
+ *
+ * They only hold weak references to the handler which is owned by this struct.
+ * Dropping it, for example when its component is destroyed, stops a running drag
+ * and removes all its listeners.
+ */
 pub struct DragCallbacks {
     pub mouse: Callback<MouseEvent>,
     pub pointer: Callback<PointerEvent>,
     pub touch: Callback<TouchEvent>,
+    _drag: Rc<dyn Any>,
+}
+impl DragCallbacks {
+    /// Callbacks for `onpointerdown`, `onmousedown` and `ontouchstart`
+    ///
+    /// Only the pointer callback is used if the browser supports pointer events.
+    #[allow(clippy::type_complexity)]
+    pub fn listeners(&self) -> (Option<Callback<PointerEvent>>, Option<Callback<MouseEvent>>, Option<Callback<TouchEvent>>) {
+        if supports_pointer_events() {
+            (Some(self.pointer.clone()), None, None)
+        } else {
+            (None, Some(self.mouse.clone()), Some(self.touch.clone()))
+        }
+    }
+}
+
+/// Check whether the browser implements pointer events
+pub fn supports_pointer_events() -> bool {
+    Reflect::has(&window(), &"PointerEvent".into()).unwrap_or(false)
+}
+
+/// Route all further events of a pointer to the element it went down on
+fn capture_pointer(event: &PointerEvent) -> Option<EventTarget> {
+    let element: Element = event.target()?.dyn_into().ok()?;
+    element.set_pointer_capture(event.pointer_id()).ok()?;
+    Some(element.into())
+}
+
+/// Something which happened to a drag
+enum DragStep {
+    Down(Event),
+    Move(Event),
+    Up(Event),
+    Cancel(Event),
+    LongPress,
+    AnimationFrame,
+}
+
+/// A handler and the steps waiting for it
+struct SharedDrag<H: DragHandler> {
+    inner: RefCell<InnerDragHandler<H>>,
+    /// Steps which happened while the handler was busy
+    queue: RefCell<VecDeque<DragStep>>,
+}
+impl <H: DragHandler> SharedDrag<H> {
+    /// Handle a step now or, if the handler is busy, as soon as it's done
+    ///
+    /// A handler might trigger events synchronously, for example through yew's scheduler,
+    /// which would otherwise need the handler while it is still borrowed.
+    fn dispatch(&self, step: DragStep) {
+        self.queue.borrow_mut().push_back(step);
+        let mut inner = match self.inner.try_borrow_mut() {
+            Ok(inner) => inner,
+            Err(_) => return,
+        };
+        loop {
+            let step = self.queue.borrow_mut().pop_front();
+            match step {
+                Some(step) => inner.handle(step),
+                None => break,
+            }
+        }
+    }
 }
 
 struct InnerDragHandler<H: DragHandler> {
-    listener: Option<(EventListener, EventListener)>,
+    /// Listeners only registered while dragging
+    listeners: Vec<EventListener>,
+    /// Identifier of the touch or pointer which started the drag
+    pointer: Option<i32>,
+    /// Event which pressed the pointer while the threshold hasn't been reached
+    pending: Option<UnifiedPointerEvent>,
+    long_press: Option<Timeout>,
+    /// Latest move waiting for the next animation frame
+    queued_move: Option<(UnifiedPointerEvent, AnimationFrame)>,
     handler: H,
-    self_ref: Weak<Mutex<InnerDragHandler<H>>>,
+    self_ref: Weak<SharedDrag<H>>,
 }
 impl <H: DragHandler> InnerDragHandler<H> {
-    fn get_down_handler(&self) -> impl Fn(&Event) {
-        let drag = self.self_ref.upgrade()
-            .expect("Self will only exists while this ref is valid");
-        move |event: &Event| {
-            let event = event.try_into()
-                .expect("Listener only registered for supported events");
-
-            let mut drag = drag.lock()
-                .expect("No threads involved");
-
-            let target: EventTarget = window()
-                .dyn_into()
-                .expect("window is an EventTarget");
-
-            drag.listener = Some((
-                EventListener::new_with_options(
-                    &target,
-                    match &event {
-                        UnifiedPointerEvent::Mouse(_) => "mousemove",
-                        UnifiedPointerEvent::Touch(_) => "touchmove",
-                        UnifiedPointerEvent::Pointer(_) => "pointermove",
-                    },
-                    EventListenerOptions::enable_prevent_default(),
-                    drag.get_move_handler()
-                ),
-                EventListener::new(
-                    &target,
-                    match &event {
-                        UnifiedPointerEvent::Mouse(_) => "mouseup",
-                        UnifiedPointerEvent::Touch(_) => "touchend",
-                        UnifiedPointerEvent::Pointer(_) => "pointerup",
-                    },
-                    drag.get_up_handler()
-                ),
-            ));
-
-            drag.handler.on_down(&event);
-        }
-    }
-
-    fn get_move_handler(&self) -> impl Fn(&Event) {
-        let drag = self.self_ref.upgrade()
-            .expect("Self will only exists while this ref is valid");
-        move |event: &Event| {
-            // Stop mobile browsers from scrolling
-            event.prevent_default();
-
-            let event: UnifiedPointerEvent = event.try_into()
-                .expect("Listener only registered for supported events");
-
-            let mut drag = drag.lock()
-                .expect("No threads involved™");
-
-            /*if event.buttons() == 0 {
-                drag.handler.on_up(&event);
-                drag.listener = None;
-            } else {
-                drag.handler.on_move(&event);
-            }*/
-            drag.handler.on_move(&event);
-        }
-    }
-
-    fn get_up_handler(&self) -> impl Fn(&Event) {
-        let drag = self.self_ref.upgrade()
-            .expect("Self will only exists while this ref is valid");
-        move |event: &Event| {
-            let event: UnifiedPointerEvent = event.try_into()
-                .expect("Listener only registered for supported events");
-
-            let mut drag = drag.lock()
-                .expect("No threads involved™");
-
-            drag.handler.on_move(&event);
-            drag.handler.on_up(&event);
-            drag.listener = None;
+    fn handle(&mut self, step: DragStep) {
+        // Steps queued behind the end of a drag have nothing left to do
+        let dragging = !self.listeners.is_empty();
+        match step {
+            DragStep::Down(event) => self.down(&event),
+            _ if !dragging => {},
+            DragStep::Move(event) => self.moved(&event),
+            DragStep::Up(event) => self.up(&event),
+            DragStep::Cancel(event) => self.cancel(&event),
+            DragStep::LongPress => self.begin(),
+            DragStep::AnimationFrame => self.flush_move(),
+        }
+    }
+
+    /// Listen for an event which is turned into a step
+    fn listen(&self, target: &EventTarget, event_type: &'static str, to_step: fn(&Event) -> Option<DragStep>) -> EventListener {
+        let drag = self.self_ref.clone();
+        EventListener::new_with_options(
+            target,
+            event_type,
+            EventListenerOptions::enable_prevent_default(),
+            move |event| {
+                if let (Some(drag), Some(step)) = (drag.upgrade(), to_step(event)) {
+                    drag.dispatch(step);
+                }
+            }
+        )
+    }
+
+    /// Get a callback which dispatches a step once it is called
+    fn later(&self, step: fn() -> DragStep) -> impl FnOnce() {
+        let drag = self.self_ref.clone();
+        move || {
+            if let Some(drag) = drag.upgrade() {
+                drag.dispatch(step());
+            }
+        }
+    }
+
+    fn down(&mut self, event: &Event) {
+        let event: UnifiedPointerEvent = event.try_into()
+            .expect("Listener only registered for supported events");
+
+        // Further fingers touching the element don't start another drag
+        if !self.listeners.is_empty() {
+            return;
+        }
+        self.pointer = event.identifier();
+
+        // Captured pointers don't need to be tracked through the whole window
+        let target: EventTarget = match &event {
+            UnifiedPointerEvent::Pointer(pointer) => capture_pointer(pointer),
+            _ => None,
+        }.unwrap_or_else(|| window()
+            .dyn_into()
+            .expect("window is an EventTarget"));
+
+        let (move_type, up_type, cancel_type) = match &event {
+            UnifiedPointerEvent::Mouse(_) => ("mousemove", "mouseup", None),
+            UnifiedPointerEvent::Touch(_) => ("touchmove", "touchend", Some("touchcancel")),
+            UnifiedPointerEvent::Pointer(_) => ("pointermove", "pointerup", Some("pointercancel")),
+        };
+        let mut listeners = vec![
+            self.listen(&target, move_type, |event| {
+                // Stop mobile browsers from scrolling
+                event.prevent_default();
+                Some(DragStep::Move(event.clone()))
+            }),
+            self.listen(&target, up_type, |event| Some(DragStep::Up(event.clone()))),
+            self.listen(&window(), "blur", |event| Some(DragStep::Cancel(event.clone()))),
+            self.listen(&window(), "keydown", |event| {
+                let key: &KeyboardEvent = event.dyn_ref()?;
+                if key.key() != "Escape" {
+                    return None;
+                }
+                key.prevent_default();
+                Some(DragStep::Cancel(event.clone()))
+            }),
+        ];
+        if let Some(cancel_type) = cancel_type {
+            listeners.push(self.listen(&target, cancel_type, |event| Some(DragStep::Cancel(event.clone()))));
+        }
+        self.listeners = listeners;
+
+        let options = self.handler.options();
+        if options.threshold <= 0 {
+            self.handler.on_down(&event);
+            return;
+        }
+        self.long_press = options.long_press
+            .filter(|_| event.pointer_type() == "touch")
+            .map(|millis| Timeout::new(millis, self.later(|| DragStep::LongPress)));
+        self.pending = Some(event);
+    }
+
+    fn moved(&mut self, event: &Event) {
+        let event = match self.tracked(event) {
+            Some(event) => event,
+            None => return,
+        };
+
+        if let Some(pending) = self.pending.as_ref() {
+            let threshold = self.handler.options().threshold;
+            let dx = event.client_x() - pending.client_x();
+            let dy = event.client_y() - pending.client_y();
+            if dx.abs() <= threshold && dy.abs() <= threshold {
+                return;
+            }
+            self.begin();
+        }
+
+        // Pointers may move several times per frame, only the latest position matters
+        let frame = match self.queued_move.take() {
+            Some((_, frame)) => frame,
+            None => {
+                let later = self.later(|| DragStep::AnimationFrame);
+                request_animation_frame(move |_| later())
+            }
+        };
+        self.queued_move = Some((event, frame));
+    }
+
+    fn up(&mut self, event: &Event) {
+        let event = match self.tracked(event) {
+            Some(event) => event,
+            None => return,
+        };
+
+        if self.pending.is_some() {
+            self.handler.on_click(&event);
+        } else {
+            self.handler.on_move(&event);
+            self.handler.on_up(&event);
+        }
+        self.end();
+    }
+
+    fn cancel(&mut self, event: &Event) {
+        let is_pointer = event.is_instance_of::<TouchEvent>() || event.is_instance_of::<PointerEvent>();
+        if is_pointer && self.tracked(event).is_none() {
+            return;
+        }
+
+        if self.pending.is_none() {
+            self.handler.on_cancel();
+        }
+        self.end();
+    }
+
+    /// Begin a drag whose threshold has been reached
+    fn begin(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.long_press = None;
+            self.handler.on_down(&pending);
+        }
+    }
+
+    /// Pass a queued move on to the handler
+    fn flush_move(&mut self) {
+        if let Some((event, _frame)) = self.queued_move.take() {
+            self.handler.on_move(&event);
+        }
+    }
+
+    /// Stop listening after a drag or click
+    fn end(&mut self) {
+        self.queued_move = None;
+        self.listeners.clear();
+        self.pending = None;
+        self.long_press = None;
+    }
+
+    /// Convert an event if it belongs to the touch or pointer which started the drag
+    ///
+    /// Other fingers touching the screen are ignored.
+    fn tracked(&self, event: &Event) -> Option<UnifiedPointerEvent> {
+        if let Some(touch_event) = event.dyn_ref::<TouchEvent>() {
+            let touches = touch_event.changed_touches();
+            return (0..touches.length())
+                .filter_map(|index| touches.get(index))
+                .find(|touch| Some(touch.identifier()) == self.pointer)
+                .map(UnifiedPointerEvent::Touch);
         }
+        let event: UnifiedPointerEvent = event.try_into().ok()?;
+        (event.identifier() == self.pointer).then_some(event)
     }
 }
 
@@ -159,15 +392,16 @@ impl <'a> TryFrom<&'a Event> for UnifiedPointerEvent {
     type Error = &'a Event;
 
     fn try_from(e: &'a Event) -> Result<Self, Self::Error> {
+        // Pointer events are mouse events as well
+        if let Some(pointer) = e.dyn_ref::<PointerEvent>() {
+            return Ok(pointer.into());
+        }
         if let Some(mouse) = e.dyn_ref::<MouseEvent>() {
             return Ok(mouse.into());
         }
         if let Some(touch) = e.dyn_ref::<TouchEvent>() {
             return Ok(touch.into());
         }
-        if let Some(pointer) = e.dyn_ref::<PointerEvent>() {
-            return Ok(pointer.into());
-        }
         return Err(e);
     }
 }
@@ -190,4 +424,33 @@ impl UnifiedPointerEvent {
     forward_method!(page_x, i32);
     forward_method!(page_y, i32);
     forward_method!(target, Option<EventTarget>);
+
+    /// Identifier of the touch or pointer, mice don't have one
+    pub fn identifier(&self) -> Option<i32> {
+        match self {
+            UnifiedPointerEvent::Mouse(_) => None,
+            UnifiedPointerEvent::Touch(e) => Some(e.identifier()),
+            UnifiedPointerEvent::Pointer(e) => Some(e.pointer_id()),
+        }
+    }
+
+    /// Pressure between 0 and 1
+    ///
+    /// Devices without pressure report 0.5 while a button is pressed.
+    pub fn pressure(&self) -> f32 {
+        match self {
+            UnifiedPointerEvent::Mouse(e) => if e.buttons() == 0 { 0.0 } else { 0.5 },
+            UnifiedPointerEvent::Touch(e) => e.force(),
+            UnifiedPointerEvent::Pointer(e) => e.pressure(),
+        }
+    }
+
+    /// Kind of device like `"mouse"`, `"pen"` or `"touch"`
+    pub fn pointer_type(&self) -> String {
+        match self {
+            UnifiedPointerEvent::Mouse(_) => "mouse".to_string(),
+            UnifiedPointerEvent::Touch(_) => "touch".to_string(),
+            UnifiedPointerEvent::Pointer(e) => e.pointer_type(),
+        }
+    }
 }
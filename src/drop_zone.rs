@@ -1,42 +1,242 @@
+use std::rc::Rc;
 use yew::prelude::*;
+use wasm_bindgen::JsValue;
+use web_sys::{DataTransfer, Element, Event};
+use gloo::events::EventListener;
+use gloo::utils::document;
+use crate::payload::{DragPayload, PayloadKind, PointerDetail, POINTER_DROP, POINTER_ENTER, POINTER_LEAVE, POINTER_OVER};
+
+/// Mime types a drop zone takes in order of preference
+#[derive(Clone, PartialEq, Debug)]
+pub struct Accepts(Rc<[String]>);
+impl Accepts {
+    pub fn new<T: Into<String>>(mime_types: impl IntoIterator<Item=T>) -> Self {
+        Accepts(mime_types.into_iter().map(Into::into).collect())
+    }
+
+    /// Take every mime type of some payload kinds
+    pub fn kinds(kinds: &[PayloadKind]) -> Self {
+        Accepts::new(kinds.iter().flat_map(|kind| kind.mime_types().iter().copied()))
+    }
+
+    /// Pick the preferred type a drag offers
+    pub fn offered(&self, dt: &DataTransfer) -> Option<&str> {
+        let types = dt.types();
+        self.0.iter()
+            .find(|mime| types.includes(&JsValue::from_str(mime), 0))
+            .map(String::as_str)
+    }
+
+    /// Whether a pointer drag's payload is taken
+    pub fn takes(&self, payload: &DragPayload) -> bool {
+        payload.kind().mime_types().iter().any(|mime| self.0.iter().any(|accepted| accepted == mime))
+    }
+}
+
+/// Where an accepted payload is dragged over a drop zone
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DragOver {
+    /// Position as fraction of the zone's width and height
+    pub x: f64,
+    pub y: f64,
+}
 
 #[derive(Properties, PartialEq)]
 pub struct DropZoneProps {
     #[prop_or_default] pub class: Classes,
     #[prop_or_default] pub over_class: Classes,
+    /// Payloads the zone takes, everything waw knows if not set
+    #[prop_or_default] pub accepts: Option<Accepts>,
+    #[prop_or_default] pub on_over: Option<Callback<DragOver>>,
+    /// Called when an accepted payload left the zone or its drag ended somewhere else
+    #[prop_or_default] pub on_leave: Option<Callback<()>>,
+    /// Called with whatever is dropped by html's drag and drop or a `PayloadDragHandler`
+    #[prop_or_default] pub on_drop: Option<Callback<DragPayload>>,
+    #[prop_or_default] pub children: Children,
 }
+
+/**
+ * An element things can be dropped onto
+ *
+ * Enter and leave events are also fired for the zone's children, so they are counted
+ * to know whether the pointer is still over the zone.
+ */
 pub struct DropZone {
-    pub over: bool,
+    /// Number of entered elements inside the zone
+    pub entered: u32,
+    node_ref: NodeRef,
+    // Pointer drags dispatch custom events and ended drags are only seen by the document,
+    // yew can't listen for either.
+    _listeners: Vec<EventListener>,
+}
+pub enum DropZoneMsg {
+    Enter,
+    Leave,
+    Over(i32, i32),
+    Drop(DragPayload),
+    Reset,
+    /// One of the `POINTER_...` events, which still has to be checked against `accepts`
+    Pointer(&'static str, PointerDetail),
 }
+
 impl Component for DropZone {
-    type Message = bool;
+    type Message = DropZoneMsg;
     type Properties = DropZoneProps;
 
     fn create(_ctx: &Context<Self>) -> Self {
         DropZone {
-            over: false,
+            entered: 0,
+            node_ref: NodeRef::default(),
+            _listeners: Vec::new(),
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
-        self.over = msg;
-        true
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let props = ctx.props();
+        match msg {
+            DropZoneMsg::Enter => {
+                self.entered += 1;
+                self.entered == 1
+            }
+            DropZoneMsg::Leave => {
+                if self.entered == 0 {
+                    return false;
+                }
+                self.entered -= 1;
+                if self.entered == 0 {
+                    self.leave(ctx);
+                    return true;
+                }
+                false
+            }
+            DropZoneMsg::Over(client_x, client_y) => {
+                let element = self.node_ref.cast::<Element>();
+                if let (Some(callback), Some(element)) = (props.on_over.as_ref(), element) {
+                    let rect = element.get_bounding_client_rect();
+                    callback.emit(DragOver {
+                        x: (client_x as f64 - rect.x()) / rect.width(),
+                        y: (client_y as f64 - rect.y()) / rect.height(),
+                    });
+                }
+                false
+            }
+            DropZoneMsg::Drop(payload) => {
+                self.entered = 0;
+                if let Some(callback) = props.on_drop.as_ref() {
+                    callback.emit(payload);
+                }
+                true
+            }
+            DropZoneMsg::Reset => {
+                if self.entered == 0 {
+                    return false;
+                }
+                self.entered = 0;
+                self.leave(ctx);
+                true
+            }
+            DropZoneMsg::Pointer(event_type, detail) => {
+                let taken = props.on_drop.is_some()
+                    && props.accepts.as_ref().is_none_or(|accepts| accepts.takes(&detail.payload));
+                if !taken {
+                    return false;
+                }
+                let msg = match event_type {
+                    POINTER_ENTER => DropZoneMsg::Enter,
+                    POINTER_LEAVE => DropZoneMsg::Leave,
+                    POINTER_OVER => DropZoneMsg::Over(detail.x, detail.y),
+                    _ => DropZoneMsg::Drop(detail.payload),
+                };
+                self.update(ctx, msg)
+            }
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let props = ctx.props();
+        // Only the types are available before the drop, the data afterwards
+        let droppable = props.on_drop.is_some();
+        let accepts = props.accepts.clone();
+        let taken = move |event: &DragEvent| event.data_transfer().filter(|dt| droppable && match accepts.as_ref() {
+            Some(accepts) => accepts.offered(dt).is_some(),
+            None => PayloadKind::of(dt).is_some(),
+        });
+        let accepts = props.accepts.clone();
+        let read = move |dt: &DataTransfer| match accepts.as_ref() {
+            Some(accepts) => DragPayload::read_as(dt, accepts.offered(dt)?),
+            None => DragPayload::read(dt),
+        };
         return html!{
             <div
+                ref={self.node_ref.clone()}
                 class={
-                    if self.over {
+                    if self.entered > 0 {
                         classes!(props.class.clone(), props.over_class.clone())
                     } else {
                         classes!(props.class.clone())
                     }
                 }
-                ondragenter={ctx.link().callback(|_| true)}
-                ondragleave={ctx.link().callback(|_| false)}
-            />
+                // Browsers only allow drops if `dragenter` and `dragover` are prevented
+                ondragenter={ctx.link().batch_callback({
+                    let taken = taken.clone();
+                    move |event: DragEvent| taken(&event).map(|_| {
+                        event.prevent_default();
+                        DropZoneMsg::Enter
+                    })
+                })}
+                ondragover={ctx.link().batch_callback({
+                    let taken = taken.clone();
+                    move |event: DragEvent| taken(&event).map(|_| {
+                        event.prevent_default();
+                        DropZoneMsg::Over(event.client_x(), event.client_y())
+                    })
+                })}
+                ondragleave={ctx.link().batch_callback({
+                    let taken = taken.clone();
+                    move |event: DragEvent| taken(&event).map(|_| DropZoneMsg::Leave)
+                })}
+                ondrop={ctx.link().batch_callback(move |event: DragEvent| {
+                    let payload = read(&taken(&event)?)?;
+                    event.prevent_default();
+                    Some(DropZoneMsg::Drop(payload))
+                })}
+            >
+                {for props.children.iter()}
+            </div>
+        };
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if !first_render {
+            return;
+        }
+        let element = match self.node_ref.cast::<Element>() {
+            Some(element) => element,
+            None => return,
+        };
+        let listen = |target: &web_sys::EventTarget, event_type, to_msg: fn(&Event) -> Option<DropZoneMsg>| {
+            let link = ctx.link().clone();
+            EventListener::new(target, event_type, move |event| {
+                if let Some(msg) = to_msg(event) {
+                    link.send_message(msg);
+                }
+            })
         };
+        self._listeners = vec![
+            listen(&element, POINTER_ENTER, |event| PointerDetail::read(event).map(|detail| DropZoneMsg::Pointer(POINTER_ENTER, detail))),
+            listen(&element, POINTER_LEAVE, |event| PointerDetail::read(event).map(|detail| DropZoneMsg::Pointer(POINTER_LEAVE, detail))),
+            listen(&element, POINTER_OVER, |event| PointerDetail::read(event).map(|detail| DropZoneMsg::Pointer(POINTER_OVER, detail))),
+            listen(&element, POINTER_DROP, |event| PointerDetail::read(event).map(|detail| DropZoneMsg::Pointer(POINTER_DROP, detail))),
+            listen(&document(), "dragend", |_| Some(DropZoneMsg::Reset)),
+            listen(&document(), "drop", |_| Some(DropZoneMsg::Reset)),
+            listen(&document(), POINTER_DROP, |_| Some(DropZoneMsg::Reset)),
+        ];
     }
-}
\ No newline at end of file
+}
+impl DropZone {
+    fn leave(&self, ctx: &Context<Self>) {
+        if let Some(callback) = ctx.props().on_leave.as_ref() {
+            callback.emit(());
+        }
+    }
+}
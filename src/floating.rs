@@ -1,12 +1,13 @@
+use std::fmt;
 use web_sys::DragEvent;
 use yew::prelude::*;
 use crate::anchor::Anchor;
 
 #[derive(Copy, Clone, PartialEq)]
 pub struct WindowId(u32);
-impl ToString for WindowId {
-    fn to_string(&self) -> String {
-        format!("waw-window-{}", self.0)
+impl fmt::Display for WindowId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "waw-window-{}", self.0)
     }
 }
 impl From<u32> for WindowId {
@@ -22,6 +23,9 @@ pub struct FloatingProps {
     pub min_width: i32,
     #[prop_or(0)]
     pub min_height: i32,
+    /// Resize only once the pointer is released
+    #[prop_or(false)]
+    pub ghost: bool,
 }
 pub struct Floating {
     pub x: i32,
@@ -89,6 +93,7 @@ impl Component for Floating {
         let on_move = |pos| ctx.link().callback(move |(dx, dy)|
             FloatingMsg::Resize(pos, dx, dy)
         );
+        let ghost = ctx.props().ghost;
 
         return html! {
             <div class="waw-window" style={
@@ -98,18 +103,18 @@ impl Component for Floating {
             ondragstart={|event: DragEvent| event.prevent_default()}
             >
                 <div id={ctx.props().id.to_string()} class="waw-body">
-                    {format!("Hello Window {}", ctx.props().id.to_string())}
+                    {format!("Hello Window {}", ctx.props().id)}
                 </div>
 
-                <Anchor class={"waw-title"} on_move={on_move(Title)}/>
-                <Anchor class={"waw-n"} on_move={on_move(N)}/>
-                <Anchor class={"waw-s"} on_move={on_move(S)}/>
-                <Anchor class={"waw-w"} on_move={on_move(W)}/>
-                <Anchor class={"waw-e"} on_move={on_move(E)}/>
-                <Anchor class={"waw-nw"} on_move={on_move(NW)}/>
-                <Anchor class={"waw-ne"} on_move={on_move(NE)}/>
-                <Anchor class={"waw-sw"} on_move={on_move(SW)}/>
-                <Anchor class={"waw-se"} on_move={on_move(SE)}/>
+                <Anchor class={"waw-title"} on_move={on_move(Title)} ghost={ghost}/>
+                <Anchor class={"waw-n"} on_move={on_move(N)} ghost={ghost}/>
+                <Anchor class={"waw-s"} on_move={on_move(S)} ghost={ghost}/>
+                <Anchor class={"waw-w"} on_move={on_move(W)} ghost={ghost}/>
+                <Anchor class={"waw-e"} on_move={on_move(E)} ghost={ghost}/>
+                <Anchor class={"waw-nw"} on_move={on_move(NW)} ghost={ghost}/>
+                <Anchor class={"waw-ne"} on_move={on_move(NE)} ghost={ghost}/>
+                <Anchor class={"waw-sw"} on_move={on_move(SW)} ghost={ghost}/>
+                <Anchor class={"waw-se"} on_move={on_move(SE)} ghost={ghost}/>
             </div>
         };
     }
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::de::value::Error as ValueError;
+use js_sys::Function;
+use web_sys::KeyboardEvent;
+use crate::screen::DockPosition;
+
+/**
+ * A key combination written like `Ctrl+Alt+ArrowLeft` or `Alt+1`
+ *
+ * Keys are named like `KeyboardEvent.key` and single characters are case insensitive.
+ */
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+    pub key: String,
+}
+impl FromStr for KeyCombo {
+    type Err = String;
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        // "+" itself might be the key
+        let (modifiers, key) = match string.strip_suffix('+') {
+            Some(modifiers) if modifiers.is_empty() || modifiers.ends_with('+') => (modifiers, "+"),
+            _ => string.rsplit_once('+').unwrap_or(("", string)),
+        };
+        if key.is_empty() {
+            return Err(format!("Missing key in '{}'", string));
+        }
+
+        let mut combo = KeyCombo { key: normalize_key(key), ..Default::default() };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => combo.ctrl = true,
+                "alt" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "meta" | "cmd" | "super" => combo.meta = true,
+                _ => return Err(format!("Unknown modifier '{}' in '{}'", modifier, string)),
+            }
+        }
+        Ok(combo)
+    }
+}
+impl KeyCombo {
+    /// Get the combos an event matches
+    ///
+    /// Modifiers like alt change the produced character on some layouts,
+    /// so the physical key is tried as well.
+    pub fn from_event(event: &KeyboardEvent) -> impl Iterator<Item=KeyCombo> {
+        let combo = |key: &str| KeyCombo {
+            ctrl: event.ctrl_key(),
+            alt: event.alt_key(),
+            shift: event.shift_key(),
+            meta: event.meta_key(),
+            key: normalize_key(key),
+        };
+        let by_key = combo(&event.key());
+        let code = event.code();
+        let by_code = if let Some(letter) = code.strip_prefix("Key") {
+            Some(letter)
+        } else if let Some(digit) = code.strip_prefix("Digit") {
+            Some(digit)
+        } else if code == "Backquote" {
+            Some("`")
+        } else {
+            None
+        }.map(combo);
+        std::iter::once(by_key).chain(by_code)
+    }
+}
+fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+/// What a key combination does
+pub enum KeyAction {
+    /// Focus the next visible window
+    CycleFocus,
+    /// Focus the previous visible window
+    CycleFocusBackwards,
+    /// Show or hide the n-th window in the taskbar (starting at 1)
+    ToggleWindow(usize),
+    /// Move the focused window into a dock
+    MoveFocused(DockPosition),
+    /// Maximize the focused window or restore the maximized one
+    ToggleMaximize,
+    /// Call a javascript function with the focused window's `<div>`
+    Custom(Function),
+}
+impl FromStr for KeyAction {
+    type Err = String;
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = string.split_once(':').unwrap_or((string, ""));
+        Ok(match (name, argument) {
+            ("cycleFocus", "") => KeyAction::CycleFocus,
+            ("cycleFocusBackwards", "") => KeyAction::CycleFocusBackwards,
+            ("toggleMaximize", "") => KeyAction::ToggleMaximize,
+            ("toggleWindow", n) => KeyAction::ToggleWindow(n.parse().ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Invalid window number in '{}'", string))?),
+            ("moveFocused", dock) => KeyAction::MoveFocused(DockPosition::deserialize(dock.into_deserializer())
+                .map_err(|_: ValueError| format!("Invalid dock in '{}'", string))?),
+            _ => return Err(format!("Unknown action '{}'", string)),
+        })
+    }
+}
+
+/**
+ * Maps key combinations to actions
+ *
+ * The default bindings are:
+ * - ``Alt+` `` and ``Alt+Shift+` `` cycle the focus through all visible windows
+ * - `Alt+1` to `Alt+9` toggle the windows in the taskbar
+ * - `Ctrl+Alt+Arrow...` and `Ctrl+Alt+Enter` move the focused window into a dock
+ * - `Alt+Enter` maximizes the focused window or restores it
+ */
+pub struct Keymap(HashMap<KeyCombo, KeyAction>);
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Keymap(HashMap::new());
+        let mut bind = |combo: &str, action: KeyAction| {
+            keymap.bind(combo.parse().expect("Default bindings are valid"), Some(action));
+        };
+
+        bind("Alt+`", KeyAction::CycleFocus);
+        bind("Alt+Shift+`", KeyAction::CycleFocusBackwards);
+        for n in 1..=9 {
+            bind(&format!("Alt+{}", n), KeyAction::ToggleWindow(n));
+        }
+        bind("Ctrl+Alt+ArrowUp", KeyAction::MoveFocused(DockPosition::Top));
+        bind("Ctrl+Alt+ArrowLeft", KeyAction::MoveFocused(DockPosition::Left));
+        bind("Ctrl+Alt+ArrowDown", KeyAction::MoveFocused(DockPosition::Bottom));
+        bind("Ctrl+Alt+ArrowRight", KeyAction::MoveFocused(DockPosition::Right));
+        bind("Ctrl+Alt+Enter", KeyAction::MoveFocused(DockPosition::Center));
+        bind("Alt+Enter", KeyAction::ToggleMaximize);
+        keymap
+    }
+}
+impl Keymap {
+    /// Add, replace or remove (`None`) a binding
+    pub fn bind(&mut self, combo: KeyCombo, action: Option<KeyAction>) {
+        match action {
+            Some(action) => self.0.insert(combo, action),
+            None => self.0.remove(&combo),
+        };
+    }
+
+    pub fn lookup(&self, event: &KeyboardEvent) -> Option<&KeyAction> {
+        KeyCombo::from_event(event).find_map(|combo| self.0.get(&combo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(ctrl: bool, alt: bool, shift: bool, meta: bool, key: &str) -> KeyCombo {
+        KeyCombo { ctrl, alt, shift, meta, key: key.to_string() }
+    }
+
+    #[test]
+    fn parse_combo() {
+        assert_eq!("Ctrl+Alt+ArrowLeft".parse(), Ok(combo(true, true, false, false, "ArrowLeft")));
+        assert_eq!("alt+1".parse(), Ok(combo(false, true, false, false, "1")));
+        assert_eq!("Shift+Cmd+K".parse(), Ok(combo(false, false, true, true, "k")));
+        assert_eq!("Enter".parse(), Ok(combo(false, false, false, false, "Enter")));
+        assert_eq!("Alt+`".parse(), Ok(combo(false, true, false, false, "`")));
+    }
+
+    #[test]
+    fn parse_plus_key() {
+        assert_eq!("+".parse(), Ok(combo(false, false, false, false, "+")));
+        assert_eq!("Ctrl++".parse(), Ok(combo(true, false, false, false, "+")));
+        assert_eq!("Ctrl+Shift++".parse(), Ok(combo(true, false, true, false, "+")));
+    }
+
+    #[test]
+    fn parse_invalid_combo() {
+        assert!("".parse::<KeyCombo>().is_err());
+        assert!("Alt+".parse::<KeyCombo>().is_err());
+        assert!("Hyper+x".parse::<KeyCombo>().is_err());
+    }
+
+    #[test]
+    fn parse_action() {
+        assert!(matches!("cycleFocus".parse(), Ok(KeyAction::CycleFocus)));
+        assert!(matches!("cycleFocusBackwards".parse(), Ok(KeyAction::CycleFocusBackwards)));
+        assert!(matches!("toggleMaximize".parse(), Ok(KeyAction::ToggleMaximize)));
+        assert!(matches!("toggleWindow:3".parse(), Ok(KeyAction::ToggleWindow(3))));
+        assert!(matches!("moveFocused:left".parse(), Ok(KeyAction::MoveFocused(DockPosition::Left))));
+        assert!(matches!("moveFocused:center".parse(), Ok(KeyAction::MoveFocused(DockPosition::Center))));
+    }
+
+    #[test]
+    fn parse_invalid_action() {
+        for action in ["", "toggleWindow:0", "toggleWindow:", "toggleWindow:-1", "toggleWindow",
+                       "moveFocused:middle", "moveFocused", "cycleFocus:1", "close"] {
+            assert!(action.parse::<KeyAction>().is_err(), "{} should be rejected", action);
+        }
+    }
+}
@@ -1,6 +1,10 @@
+// `return html!{..};` is the house style and yew's `html!` expands props to bare statements
+#![allow(clippy::needless_return, clippy::unnecessary_operation)]
+
 use wasm_bindgen::prelude::*;
-use web_sys::{Element, HtmlElement};
+use web_sys::{DataTransfer, Element, HtmlElement};
 use yew::prelude::*;
+use gloo::utils::format::JsValueSerdeExt;
 
 pub mod dragndrop;
 pub mod promise;
@@ -9,9 +13,14 @@ pub mod drop_zone;
 pub mod floating;
 pub mod window;
 pub mod screen;
+pub mod tiling;
+pub mod keymap;
+pub mod payload;
 
 use crate::promise::{Promise, PendingPromise};
-use crate::screen::{Screen, ScreenMsg, ScreenProps};
+use crate::screen::{Screen, ScreenMsg, ScreenProps, ScreenOptions, WindowTarget};
+use crate::keymap::KeyAction;
+use crate::payload::DragPayload;
 
 
 #[wasm_bindgen(js_name="Screen")]
@@ -21,11 +30,16 @@ pub struct ScreenHandle(AppHandle<Screen>);
 impl ScreenHandle {
 
     #[wasm_bindgen(constructor)]
-    pub fn new(parent: HtmlElement) -> ScreenHandle {
+    pub fn new(parent: HtmlElement, options: JsValue) -> Result<ScreenHandle, JsValue> {
+        let options: ScreenOptions = options.try_into()
+            .map_err(|_| JsValue::from("Invalid options"))?;
         let element: &Element = &parent;
-        yew::start_app_with_props_in_element(element.clone(), ScreenProps { parent }).into()
+        Ok(yew::start_app_with_props_in_element(element.clone(), ScreenProps { parent, options }).into())
     }
 
+    /// Re-read the parent's size
+    ///
+    /// The parent is observed for size changes, so this is only a fallback.
     pub fn resize(&self) {
         self.0.send_message(ScreenMsg::Resize);
     }
@@ -41,12 +55,136 @@ impl ScreenHandle {
         promise
     }
 
+    #[wasm_bindgen(js_name="setDockMode")]
+    pub fn set_dock_mode(&self, dock: JsValue, mode: JsValue) -> Result<(), JsValue> {
+        let dock = dock.into_serde().map_err(|_| JsValue::from("Invalid dock"))?;
+        let mode = mode.into_serde().map_err(|_| JsValue::from("Invalid mode"))?;
+        self.0.send_message(ScreenMsg::SetDockMode(dock, mode));
+        Ok(())
+    }
+
+    /// Let a dock take no space and only slide out over the center when activated
+    #[wasm_bindgen(js_name="setAutoHide")]
+    pub fn set_auto_hide(&self, dock: JsValue, auto_hide: bool) -> Result<(), JsValue> {
+        let dock = dock.into_serde().map_err(|_| JsValue::from("Invalid dock"))?;
+        self.0.send_message(ScreenMsg::SetAutoHide(dock, auto_hide));
+        Ok(())
+    }
+
+    /// Move a window with its `<div>` into another screen
+    ///
+    /// Without a `dock` the window keeps its current one, if it is open.
+    #[wasm_bindgen(js_name="transferWindow")]
+    pub fn transfer_window(&self, div: Element, target: &ScreenHandle, dock: JsValue) -> Result<(), JsValue> {
+        let dock = if dock.is_undefined() || dock.is_null() {
+            None
+        } else {
+            Some(dock.into_serde().map_err(|_| JsValue::from("Invalid dock"))?)
+        };
+        let id = self.window_id(&div)?;
+        let target_screen = (*target.0).clone();
+        self.0.send_message(ScreenMsg::TransferWindow(id, target_screen, dock.map(WindowTarget::Dock)));
+        Ok(())
+    }
+
+    /// Move a window into a separate browser window
+    ///
+    /// It is docked again when the browser window is closed.
+    #[wasm_bindgen(js_name="popOut")]
+    pub fn pop_out(&self, div: Element) -> Result<(), JsValue> {
+        let id = self.window_id(&div)?;
+        self.0.send_message(ScreenMsg::PopOut(id));
+        Ok(())
+    }
+
+    /// Close a popped out window's browser window and dock it again
+    #[wasm_bindgen(js_name="popIn")]
+    pub fn pop_in(&self, div: Element) -> Result<(), JsValue> {
+        let id = self.window_id(&div)?;
+        self.0.send_message(ScreenMsg::PopIn(id));
+        Ok(())
+    }
+
+    /// Render a window over all docks until `restore` is called
+    pub fn maximize(&self, div: Element) -> Result<(), JsValue> {
+        let id = self.window_id(&div)?;
+        self.0.send_message(ScreenMsg::Maximize(id));
+        Ok(())
+    }
+
+    /// Undo `maximize`
+    pub fn restore(&self) {
+        self.0.send_message(ScreenMsg::Restore);
+    }
+
+    /// Show a window's `<div>` using the browser's fullscreen mode
+    pub fn fullscreen(&self, div: Element) -> Result<(), JsValue> {
+        let id = self.window_id(&div)?;
+        self.0.send_message(ScreenMsg::Fullscreen(id));
+        Ok(())
+    }
+
+    /// Give a window the focus, showing it if necessary
+    ///
+    /// Focus changes are announced by a `focuschange` event on the parent element
+    /// whose `detail` is the focused window's `<div>` or `null`.
+    #[wasm_bindgen(js_name="focusWindow")]
+    pub fn focus_window(&self, div: Element) -> Result<(), JsValue> {
+        let id = self.window_id(&div)?;
+        self.0.send_message(ScreenMsg::FocusWindow(id));
+        Ok(())
+    }
+
+    /// Get the focused window's `<div>`
+    #[wasm_bindgen(js_name="focusedWindow")]
+    pub fn focused_window(&self) -> Option<Element> {
+        self.0.get_component().and_then(|screen| screen.focused_window())
+    }
+
+    /// Bind a key combination like `"Ctrl+Alt+ArrowLeft"` to an action
+    ///
+    /// The action is either a function receiving the focused window's `<div>`,
+    /// the name of a builtin action like `"moveFocused:left"` or `null` to remove the binding.
+    #[wasm_bindgen(js_name="bindKey")]
+    pub fn bind_key(&self, combo: &str, action: JsValue) -> Result<(), JsValue> {
+        let combo = combo.parse().map_err(|error: String| JsValue::from(error))?;
+        let action = if action.is_null() || action.is_undefined() {
+            None
+        } else if let Some(function) = action.dyn_ref::<js_sys::Function>() {
+            Some(KeyAction::Custom(function.clone()))
+        } else if let Some(name) = action.as_string() {
+            Some(name.parse().map_err(|error: String| JsValue::from(error))?)
+        } else {
+            return Err(JsValue::from("Invalid action"));
+        };
+        self.0.send_message(ScreenMsg::BindKey(combo, action));
+        Ok(())
+    }
+
     pub fn destroy(self) {
         self.0.destroy();
     }
 }
+impl ScreenHandle {
+    /// Look up the internal id of a window's `<div>`
+    fn window_id(&self, div: &Element) -> Result<usize, JsValue> {
+        self.0.get_component()
+            .and_then(|screen| screen.find_window(div))
+            .ok_or_else(|| JsValue::from("Unknown window"))
+    }
+}
 impl From<AppHandle<Screen>> for ScreenHandle {
     fn from(handle: AppHandle<Screen>) -> Self {
         ScreenHandle(handle)
     }
+}
+
+/// Drag some json which screens recognize as a custom payload
+///
+/// Call this in a `dragstart` listener with the event's `dataTransfer`.
+#[wasm_bindgen(js_name="setDragPayload")]
+pub fn set_drag_payload(data_transfer: DataTransfer, data: JsValue) -> Result<(), JsValue> {
+    let data = data.into_serde().map_err(|_| JsValue::from("Payload has to be json"))?;
+    DragPayload::Custom(data).write(&data_transfer);
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,334 @@
+/**
+ * Everything which can be dragged onto a screen
+ *
+ * Payloads created by waw travel as json, so they survive being dragged between
+ * screens or browser windows. Windows can only move between screens of the same page,
+ * the screen ids tell them apart from windows of other pages. Each kind has its own
+ * mime type because only the types, but not the data, can be read before the drop.
+ *
+ * Pointer drags (see `PayloadDragHandler`) don't have a `DataTransfer`,
+ * they dispatch custom events on the element under the pointer instead.
+ * Their `detail` is the json of a `PointerDetail`.
+ */
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CustomEvent, CustomEventInit, DataTransfer, Element, Event, File};
+use gloo::utils::document;
+use gloo::utils::format::JsValueSerdeExt;
+use crate::dragndrop::{DragHandler, DragOptions, UnifiedPointerEvent};
+
+/// Custom event dispatched on elements a pointer drag enters
+pub const POINTER_ENTER: &str = "waw-dragenter";
+/// Custom event dispatched on elements a pointer drag moves over
+pub const POINTER_OVER: &str = "waw-dragover";
+/// Custom event dispatched on elements a pointer drag left
+pub const POINTER_LEAVE: &str = "waw-dragleave";
+/// Custom event dispatched on the element a pointer drag ended over
+pub const POINTER_DROP: &str = "waw-drop";
+
+/**
+ * A window being dragged
+ *
+ * It carries the screen's random id to tell apart windows from different screens,
+ * including those of other pages.
+ */
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub struct DraggedWindow {
+    pub screen: u64,
+    pub window: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum DragPayload {
+    /// A window's taskbar entry
+    Window(DraggedWindow),
+    /// A window's tab in a dock
+    Tab(DraggedWindow),
+    /// Any json set by javascript
+    Custom(serde_json::Value),
+    /// Files dragged from the operating system
+    #[serde(skip)]
+    Files(Vec<File>),
+    /// Text or links dragged from other applications
+    #[serde(skip)]
+    Text(String),
+}
+
+/// What the custom events of a pointer drag carry
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PointerDetail {
+    pub payload: DragPayload,
+    /// The pointer's client coordinates
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PayloadKind {
+    Window,
+    Tab,
+    Custom,
+    Files,
+    Text,
+}
+
+impl PayloadKind {
+    const ALL: [PayloadKind; 5] = [
+        PayloadKind::Window,
+        PayloadKind::Tab,
+        PayloadKind::Custom,
+        PayloadKind::Files,
+        PayloadKind::Text,
+    ];
+
+    /// Types in a `DataTransfer` which carry this kind
+    pub fn mime_types(self) -> &'static [&'static str] {
+        match self {
+            PayloadKind::Window => &["application/waw-window"],
+            PayloadKind::Tab    => &["application/waw-tab"],
+            PayloadKind::Custom => &["application/waw-custom"],
+            PayloadKind::Files  => &["Files"],
+            PayloadKind::Text   => &["text/uri-list", "text/plain"],
+        }
+    }
+
+    /// Check what is being dragged
+    ///
+    /// Unlike `DragPayload::read` this also works before the drop.
+    pub fn of(dt: &DataTransfer) -> Option<PayloadKind> {
+        let types = dt.types();
+        PayloadKind::ALL.into_iter().find(|kind| {
+            kind.mime_types().iter().any(|mime| types.includes(&JsValue::from_str(mime), 0))
+        })
+    }
+
+    /// Get the kind carried by a mime type
+    pub fn from_mime(mime: &str) -> Option<PayloadKind> {
+        PayloadKind::ALL.into_iter().find(|kind| kind.mime_types().contains(&mime))
+    }
+
+    /// Whether the payload moves a window
+    pub fn is_window(self) -> bool {
+        matches!(self, PayloadKind::Window | PayloadKind::Tab)
+    }
+}
+
+impl DragPayload {
+    pub fn kind(&self) -> PayloadKind {
+        match self {
+            DragPayload::Window(_) => PayloadKind::Window,
+            DragPayload::Tab(_)    => PayloadKind::Tab,
+            DragPayload::Custom(_) => PayloadKind::Custom,
+            DragPayload::Files(_)  => PayloadKind::Files,
+            DragPayload::Text(_)   => PayloadKind::Text,
+        }
+    }
+
+    /// Get the window a payload moves
+    pub fn window(&self) -> Option<DraggedWindow> {
+        match self {
+            DragPayload::Window(window) | DragPayload::Tab(window) => Some(*window),
+            _ => None,
+        }
+    }
+
+    /// Convert the payload's data for javascript
+    ///
+    /// Files become an array of `File`s, text a string and everything else its json.
+    pub fn data(&self) -> JsValue {
+        match self {
+            DragPayload::Files(files) => files.iter().collect::<js_sys::Array>().into(),
+            DragPayload::Text(text) => JsValue::from_str(text),
+            DragPayload::Custom(value) => JsValue::from_serde(value).unwrap_or(JsValue::NULL),
+            DragPayload::Window(window) | DragPayload::Tab(window) => JsValue::from_serde(window).unwrap_or(JsValue::NULL),
+        }
+    }
+
+    /// Read the payload of a drop
+    ///
+    /// Links are preferred over plain text.
+    pub fn read(dt: &DataTransfer) -> Option<DragPayload> {
+        PayloadKind::of(dt)?.mime_types().iter().find_map(|mime| DragPayload::read_as(dt, mime))
+    }
+
+    /// Read the payload of a drop from one specific type
+    pub fn read_as(dt: &DataTransfer, mime: &str) -> Option<DragPayload> {
+        match PayloadKind::from_mime(mime)? {
+            PayloadKind::Files => {
+                let files = dt.files()?;
+                Some(DragPayload::Files((0..files.length()).filter_map(|index| files.get(index)).collect()))
+            }
+            PayloadKind::Text => dt.get_data(mime).ok()
+                .filter(|text| !text.is_empty())
+                .map(DragPayload::Text),
+            _ => serde_json::from_str(&dt.get_data(mime).ok()?).ok(),
+        }
+    }
+
+    /// Start dragging the payload
+    ///
+    /// Files and text can't be written, the browser provides them.
+    pub fn write(&self, dt: &DataTransfer) {
+        let data = match serde_json::to_string(self) {
+            Ok(data) => data,
+            Err(_) => {
+                gloo::console::error!("Only waw's own payloads can be written");
+                return;
+            }
+        };
+        if let Err(error) = dt.set_data(self.kind().mime_types()[0], &data) {
+            gloo::console::error!("Couldn't set drag data:", error);
+        }
+    }
+}
+
+impl PointerDetail {
+    /// Read the detail of one of the pointer drag's custom events
+    pub fn read(event: &Event) -> Option<PointerDetail> {
+        let detail = event.dyn_ref::<CustomEvent>()?.detail().as_string()?;
+        serde_json::from_str(&detail).ok()
+    }
+
+    /// Dispatch one of the pointer drag's custom events
+    fn dispatch(&self, target: &Element, event_type: &str) {
+        let init = CustomEventInit::new();
+        init.set_bubbles(true);
+        init.set_detail(&serde_json::to_string(self).unwrap_or_default().into());
+        if let Ok(event) = CustomEvent::new_with_event_init_dict(event_type, &init) {
+            let _ = target.dispatch_event(&event);
+        }
+    }
+}
+
+/**
+ * Drags a payload using pointer events
+ *
+ * HTML's drag and drop api isn't available on most touch devices,
+ * so this imitates it with the `POINTER_...` custom events.
+ */
+pub struct PayloadDragHandler {
+    detail: PointerDetail,
+    over: Option<Element>,
+}
+impl PayloadDragHandler {
+    pub fn new(payload: DragPayload) -> Self {
+        PayloadDragHandler {
+            detail: PointerDetail { payload, x: 0, y: 0 },
+            over: None,
+        }
+    }
+
+    /// Move the drag over the element below the pointer
+    fn hover(&mut self, event: &UnifiedPointerEvent) -> Option<Element> {
+        self.detail.x = event.client_x();
+        self.detail.y = event.client_y();
+        let below = document().element_from_point(self.detail.x as f32, self.detail.y as f32);
+        if below != self.over {
+            // Enter before leaving like html's drag and drop,
+            // so drop zones containing both elements stay entered
+            if let Some(entered) = below.as_ref() {
+                self.detail.dispatch(entered, POINTER_ENTER);
+            }
+            self.leave();
+            self.over = below;
+        }
+        self.over.clone()
+    }
+
+    /// Dispatch a leave for the element the drag is currently over
+    fn leave(&mut self) {
+        if let Some(left) = self.over.take() {
+            self.detail.dispatch(&left, POINTER_LEAVE);
+        }
+    }
+}
+impl DragHandler for PayloadDragHandler {
+    fn on_down(&mut self, _event: &UnifiedPointerEvent) {}
+
+    fn on_move(&mut self, event: &UnifiedPointerEvent) {
+        if let Some(over) = self.hover(event) {
+            self.detail.dispatch(&over, POINTER_OVER);
+        }
+    }
+
+    fn on_up(&mut self, event: &UnifiedPointerEvent) {
+        if let Some(over) = self.hover(event) {
+            self.detail.dispatch(&over, POINTER_DROP);
+        }
+        self.leave();
+    }
+
+    fn on_cancel(&mut self) {
+        self.leave();
+    }
+
+    fn options(&self) -> DragOptions {
+        DragOptions {
+            threshold: 5,
+            long_press: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::drop_zone::Accepts;
+
+    const WINDOW: DraggedWindow = DraggedWindow { screen: 7, window: 3 };
+
+    #[test]
+    fn round_trip() {
+        let payloads = [
+            (DragPayload::Window(WINDOW), json!({"type": "window", "data": {"screen": 7, "window": 3}})),
+            (DragPayload::Tab(WINDOW), json!({"type": "tab", "data": {"screen": 7, "window": 3}})),
+            (DragPayload::Custom(json!({"answer": 42})), json!({"type": "custom", "data": {"answer": 42}})),
+        ];
+        for (payload, expected) in payloads {
+            let value = serde_json::to_value(&payload).unwrap();
+            assert_eq!(value, expected);
+            assert_eq!(serde_json::from_value::<DragPayload>(value).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn files_and_text_are_not_serialized() {
+        assert!(serde_json::to_string(&DragPayload::Files(Vec::new())).is_err());
+        assert!(serde_json::to_string(&DragPayload::Text("text".to_string())).is_err());
+        assert!(serde_json::from_value::<DragPayload>(json!({"type": "text", "data": "text"})).is_err());
+    }
+
+    #[test]
+    fn pointer_detail_round_trip() {
+        let detail = PointerDetail { payload: DragPayload::Tab(WINDOW), x: 10, y: -5 };
+        let json = serde_json::to_string(&detail).unwrap();
+        assert_eq!(serde_json::from_str::<PointerDetail>(&json).unwrap(), detail);
+    }
+
+    #[test]
+    fn from_mime() {
+        for kind in PayloadKind::ALL {
+            for mime in kind.mime_types() {
+                assert_eq!(PayloadKind::from_mime(mime), Some(kind));
+            }
+        }
+        assert_eq!(PayloadKind::from_mime("text/html"), None);
+        assert_eq!(DragPayload::Custom(json!(null)).kind(), PayloadKind::Custom);
+        assert_eq!(DragPayload::Text(String::new()).kind(), PayloadKind::Text);
+    }
+
+    #[test]
+    fn accepts_takes() {
+        let windows = Accepts::kinds(&[PayloadKind::Window, PayloadKind::Tab]);
+        assert!(windows.takes(&DragPayload::Window(WINDOW)));
+        assert!(windows.takes(&DragPayload::Tab(WINDOW)));
+        assert!(!windows.takes(&DragPayload::Custom(json!(null))));
+
+        // Any of a kind's mime types is enough
+        let links = Accepts::new(["text/uri-list"]);
+        assert!(links.takes(&DragPayload::Text("https://example.com".to_string())));
+        assert!(!links.takes(&DragPayload::Window(WINDOW)));
+    }
+}
@@ -1,35 +1,155 @@
-use std::cmp::max;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
 use serde::Deserialize;
-use wasm_bindgen::JsCast;
-use web_sys::HtmlElement;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::closure::Closure;
+use gloo::utils::format::JsValueSerdeExt;
+use web_sys::{CustomEvent, CustomEventInit, Element, Event, EventTarget, HtmlElement, ResizeObserver};
 use gloo::events::EventListener;
 use yew::prelude::*;
+use yew::html::Scope;
 
 use crate::promise::PendingPromise;
 use crate::anchor::Anchor;
-use crate::drop_zone::DropZone;
-use crate::window::{Window, WindowInit};
+use crate::drop_zone::{Accepts, DragOver, DropZone};
+use crate::dragndrop::{DragCallbacks, DragHandler};
+use crate::payload::{DragPayload, DraggedWindow, PayloadDragHandler, PayloadKind};
+use crate::window::{Popup, Window, WindowInit};
+use crate::tiling::{SplitDirection, Tile, TileTree};
+use crate::keymap::{KeyAction, KeyCombo, Keymap};
 
 #[derive(Properties, PartialEq)]
 pub struct ScreenProps {
     pub parent: HtmlElement,
+    #[prop_or_default] pub options: ScreenOptions,
+}
+
+/**
+ * This struct directly matches the javascript object optionally passed to `new Screen`.
+ */
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct ScreenOptions {
+    pub dock_modes: PerDock<DockMode>,
+    /// Docks which take no space and slide out over the center when activated
+    pub auto_hide: PerDock<bool>,
+    pub dock_size_policy: DockSizePolicy,
+    /// Smallest size in pixels a visible dock can be resized to
+    pub min_dock_size: f64,
+    /// Largest size of a dock as fraction of the screen's width or height
+    pub max_dock_size: f64,
+    /// Size in pixels the center dock keeps in both directions
+    pub min_center_size: f64,
+    /// Sizes as fraction of the screen's width or height a dragged dock snaps to
+    pub snap_points: Vec<f64>,
+    /// Distance in pixels at which a dragged dock snaps
+    pub snap_distance: f64,
+    /// Pixels a focused dock edge or divider is moved by the arrow keys, and while holding shift
+    pub resize_step: i32,
+    pub resize_large_step: i32,
+    /// Only show an outline while dragging a dock's edge or a divider and resize once it is released
+    pub ghost_resize: bool,
+    /// Key combinations mapped to actions like `"moveFocused:left"`, `null` removes a default binding
+    pub keymap: HashMap<String, Option<String>>,
+}
+impl Default for ScreenOptions {
+    fn default() -> Self {
+        ScreenOptions {
+            dock_modes: PerDock::default(),
+            auto_hide: PerDock::default(),
+            dock_size_policy: DockSizePolicy::default(),
+            min_dock_size: 0.0,
+            max_dock_size: 1.0,
+            min_center_size: 50.0,
+            snap_points: Vec::new(),
+            snap_distance: 10.0,
+            resize_step: 10,
+            resize_large_step: 50,
+            ghost_resize: false,
+            keymap: HashMap::new(),
+        }
+    }
+}
+impl TryFrom<JsValue> for ScreenOptions {
+    type Error = serde_json::Error;
+    fn try_from(value: JsValue) -> Result<Self, Self::Error> {
+        if value.is_undefined() || value.is_null() {
+            Ok(ScreenOptions::default())
+        } else {
+            value.into_serde()
+        }
+    }
+}
+
+/**
+ * One value for each of the four side docks
+ */
+#[derive(Deserialize, Default, Copy, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PerDock<T: Default> {
+    pub top: T,
+    pub left: T,
+    pub bottom: T,
+    pub right: T,
+}
+impl <T: Default> PerDock<T> {
+    /// Convert into an array indexable by `dock as usize`
+    pub fn into_array(self) -> [T; 4] {
+        [self.top, self.left, self.bottom, self.right]
+    }
 }
 pub struct Screen {
+    pub id: u64,
     pub width: u32,
     pub height: u32,
-    pub resize_listener: EventListener, // Listen on window for size changes
-    // Listening on direct parent would require ResizeObserver,
-    // which is unstable and annoying to use in web-sys
+    pub resize_listener: ResizeListener, // Listen on parent for size changes
+    pub keydown_listener: EventListener, // Listen on document for shortcuts
+    pub keymap: Keymap,
 
-    pub windows: Vec<Window>,
+    pub windows: BTreeMap<usize, Window>,
+    pub next_window: usize,
+    // Pointer drags of the windows' taskbar entries for devices without html's drag and drop
+    pub window_drags: BTreeMap<usize, DragCallbacks>,
 
-    pub dock_sizes: [i32; 4],
+    pub dock_sizes: [f64; 4],
+    // Sizes last set by the user, which the `fixed` policy returns to after resizes
+    pub dock_preferred_sizes: [f64; 4],
+    pub dock_collapsed: [bool; 4],
+    // Dock being dragged and its size ignoring snapping
+    pub dock_drag: Option<(DockPosition, f64)>,
+    // Dock sizes and collapsed states to restore when the drag is cancelled
+    pub dock_drag_start: ([f64; 4], [bool; 4]),
     pub dock_windows: [Vec<usize>; 5],
+    pub dock_modes: [DockMode; 4],
+    pub dock_tabs: [Option<usize>; 4],
+    pub dock_auto_hide: [bool; 4],
+    pub dock_revealed: Option<DockPosition>,
     pub dock_selector: Option<(usize, i32, i32)>,
+    pub selector_focus: DockPosition,
+    pub selector_ref: NodeRef,
+    // Taskbar entry to focus after the next render
+    pub focus_taskbar: Option<usize>,
+
+    // Window rendered over all docks without changing them
+    pub maximized: Option<usize>,
 
-    // Event handler which is assigned to dragenter and dragover
-    // to make something a target for dragged windows
-    pub make_drop_target: Callback<DragEvent>,
+    // Window receiving keyboard shortcuts and whether it should get the DOM's focus after the next render
+    pub focused: Option<usize>,
+    pub focus_pending: bool,
+    // Focused window the last `focuschange` event was dispatched for
+    pub reported_focus: Option<usize>,
+
+    // Layout of the center dock and the leaf a window is currently dragged over
+    pub tiles: TileTree,
+    pub tile_hover: Option<(usize, DockPosition)>,
+    // Split being dragged, whose ratio is only applied to its element until the drag ends
+    pub tile_drag: Option<usize>,
+    pub center_ref: NodeRef,
+    pub docks_ref: NodeRef,
+    pub screen_ref: NodeRef,
+
+    // Types of drop zones taking dragged windows
+    pub window_drops: Accepts,
 }
 pub enum ScreenMsg {
     Resize,
@@ -37,20 +157,117 @@ pub enum ScreenMsg {
     MoveWindow(usize, DockPosition),
     OpenSelector(usize, i32, i32),
     CloseSelector(Option<DockPosition>),
+    FocusSelector(DockPosition),
     ToggleWindow(usize),
+    BeginResizeDock(DockPosition),
     ResizeDock(DockPosition, i32, i32),
+    EndResizeDock,
+    CancelResizeDock,
+    ToggleCollapseDock(DockPosition),
+    SetDockMode(DockPosition, DockMode),
+    SelectTab(usize),
+    ToggleCollapsed(usize),
+    DropWindow(DraggedWindow, WindowTarget),
+    TransferWindow(usize, Scope<Screen>, Option<WindowTarget>),
+    AdoptWindow(Window, Option<WindowTarget>),
+    PopOut(usize),
+    PopIn(usize),
+    SetAutoHide(DockPosition, bool),
+    RevealDock(Option<DockPosition>),
+    Maximize(usize),
+    Restore,
+    Fullscreen(usize),
+    FocusWindow(usize),
+    TrackFocus(usize),
+    KeyDown(KeyboardEvent),
+    BindKey(KeyCombo, Option<KeyAction>),
+    HoverTile(Option<(usize, DockPosition)>),
+    LeaveTile(usize),
+    DropOnTile(DraggedWindow, usize),
+    DropIntoWindow(usize, DragPayload),
+    BeginResizeTile(usize),
+    ResizeTile(usize, i32, i32),
+    EndResizeTile,
 }
+/// Where a window should be placed
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WindowTarget {
+    Dock(DockPosition),
+    /// A leaf in the center's tiling and the edge to split it at
+    Tile(usize, DockPosition),
+}
+
+thread_local! {
+    // All screens on this page to transfer windows between them
+    static SCREENS: RefCell<HashMap<u64, Scope<Screen>>> = RefCell::new(HashMap::new());
+
+    // Screen last interacted with, which handles shortcuts pressed without any focused element
+    static ACTIVE_SCREEN: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Pick a random id for a new screen
+///
+/// Payloads can also be dragged in from other tabs or browser windows running waw,
+/// so counting screens per page isn't enough to tell them apart.
+fn new_screen_id() -> u64 {
+    loop {
+        let id = (js_sys::Math::random() * (1u64 << 53) as f64) as u64;
+        if !SCREENS.with(|screens| screens.borrow().contains_key(&id)) {
+            return id;
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DockPosition {
     Top, Left, Bottom, Right, Center
 }
+
+/// How dock sizes react to the screen changing its size
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DockSizePolicy {
+    /// Keep the size in pixels, docks squeezed to keep the center's minimum size grow back later
+    Fixed,
+    /// Scale with the screen
+    #[default]
+    Proportional,
+    /// Keep the size in pixels, docks squeezed to keep the center's minimum size stay that small
+    Clamp,
+}
+
+/// How a side dock arranges multiple windows
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DockMode {
+    /// All windows next to each other
+    #[default]
+    Stack,
+    /// A tab bar and only the selected window
+    Tabs,
+    /// A header per window which collapses and expands it
+    Accordion,
+}
+
 impl DockPosition {
     #[inline]
     pub fn array() -> [DockPosition; 4] {
         use DockPosition::*;
         [Top, Left, Bottom, Right]
     }
+
+    /// Name announced by screen readers
+    pub fn label(self) -> &'static str {
+        use DockPosition::*;
+        match self {
+            Top    => "Top dock",
+            Left   => "Left dock",
+            Bottom => "Bottom dock",
+            Right  => "Right dock",
+            Center => "Center",
+        }
+    }
 }
 
 impl Component for Screen {
@@ -58,31 +275,74 @@ impl Component for Screen {
     type Properties = ScreenProps;
 
     fn create(ctx: &Context<Self>) -> Self {
+        let id = new_screen_id();
+        ACTIVE_SCREEN.with(|active| if active.get().is_none() { active.set(Some(id)) });
+        SCREENS.with(|screens| screens.borrow_mut().insert(id, ctx.link().clone()));
+
         let scope = ctx.link().clone();
-        let resize_listener =
-        EventListener::new(&gloo::utils::window(), "resize", move |_event| {
+        let parent: &HtmlElement = &ctx.props().parent;
+        make_containing_block(parent);
+        let resize_listener = ResizeListener::new(parent, move || {
             scope.send_message(ScreenMsg::Resize);
         });
+        let scope = ctx.link().clone();
+        let keydown_listener = EventListener::new(&gloo::utils::document(), "keydown", move |event| {
+            if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                scope.send_message(ScreenMsg::KeyDown(event.clone()));
+            }
+        });
+
+        let options = &ctx.props().options;
+        let mut keymap = Keymap::default();
+        for (combo, action) in options.keymap.iter() {
+            let binding = combo.parse().and_then(|combo| Ok((combo, match action {
+                Some(action) => Some(action.parse()?),
+                None => None,
+            })));
+            match binding {
+                Ok((combo, action)) => keymap.bind(combo, action),
+                Err(error) => gloo::console::error!("Invalid key binding:", error),
+            }
+        }
 
-        let parent: &HtmlElement = &ctx.props().parent;
         let width = parent.offset_width() as u32;
         let height = parent.offset_height() as u32;
         Screen {
-            width, height, resize_listener,
+            id, width, height, resize_listener, keydown_listener, keymap,
 
-            windows: Vec::new(),
+            windows: BTreeMap::new(),
+            next_window: 0,
+            window_drags: BTreeMap::new(),
 
-            dock_sizes: [height as i32 / 10, width as i32 / 10, height as i32 / 5, width as i32 / 5],
+            dock_sizes: DockPosition::array().map(|dock| default_dock_size(dock, width as f64, height as f64)),
+            dock_preferred_sizes: DockPosition::array().map(|dock| default_dock_size(dock, width as f64, height as f64)),
+            dock_collapsed: [false; 4],
+            dock_drag: None,
+            dock_drag_start: ([0.0; 4], [false; 4]),
             dock_windows: Default::default(),
+            dock_modes: options.dock_modes.into_array(),
+            dock_tabs: [None; 4],
+            dock_auto_hide: options.auto_hide.into_array(),
+            dock_revealed: None,
             dock_selector: None,
+            selector_focus: DockPosition::Center,
+            selector_ref: NodeRef::default(),
+            focus_taskbar: None,
 
-            make_drop_target: Callback::from(|event: DragEvent| {
-                if let Some(dt) = event.data_transfer() {
-                    if dt.types().includes(&"application/waw".into(), 0) {
-                        event.prevent_default();
-                    }
-                }
-            }),
+            maximized: None,
+
+            focused: None,
+            focus_pending: false,
+            reported_focus: None,
+
+            tiles: TileTree::default(),
+            tile_hover: None,
+            tile_drag: None,
+            center_ref: NodeRef::default(),
+            docks_ref: NodeRef::default(),
+            screen_ref: NodeRef::default(),
+
+            window_drops: Accepts::kinds(&[PayloadKind::Window, PayloadKind::Tab]),
         }
     }
 
@@ -91,8 +351,46 @@ impl Component for Screen {
         match msg {
             Resize => {
                 let parent = &ctx.props().parent;
-                self.width = parent.offset_width() as u32;
-                self.height = parent.offset_height() as u32;
+                let options = &ctx.props().options;
+                let (old_width, old_height) = (self.width, self.height);
+                let (width, height) = (parent.offset_width() as u32, parent.offset_height() as u32);
+                // A hidden parent has no size, scaling to it would lose the docks' sizes for good
+                if width == 0 || height == 0 {
+                    return false;
+                }
+                self.width = width;
+                self.height = height;
+
+                // Screens created in a hidden parent only now learn their size
+                use DockPosition::*;
+                for (docks, old) in [([Top, Bottom], old_height), ([Left, Right], old_width)] {
+                    if old == 0 {
+                        for dock in docks {
+                            let size = default_dock_size(dock, width as f64, height as f64);
+                            self.dock_sizes[dock as usize] = size;
+                            self.dock_preferred_sizes[dock as usize] = size;
+                        }
+                    }
+                }
+
+                match options.dock_size_policy {
+                    // Docks squeezed by an earlier resize grow back once there is room
+                    DockSizePolicy::Fixed => self.dock_sizes = self.dock_preferred_sizes,
+                    DockSizePolicy::Clamp => {},
+                    DockSizePolicy::Proportional => {
+                        if old_height > 0 {
+                            let scale = self.height as f64 / old_height as f64;
+                            self.dock_sizes[Top as usize] *= scale;
+                            self.dock_sizes[Bottom as usize] *= scale;
+                        }
+                        if old_width > 0 {
+                            let scale = self.width as f64 / old_width as f64;
+                            self.dock_sizes[Left as usize] *= scale;
+                            self.dock_sizes[Right as usize] *= scale;
+                        }
+                    },
+                }
+                self.constrain_docks(options, None);
                 true
             }
             NewWindow(promise, init) => {
@@ -100,37 +398,40 @@ impl Component for Screen {
 
                 promise.resolve(window.div.clone());
 
-                self.windows.push(window);
+                self.windows.insert(self.next_window, window);
+                self.track_window_drag(self.next_window);
+                self.next_window += 1;
                 true
             }
             MoveWindow(id, dock) => {
                 self.dock_selector = None;
-                if let Some(window) = self.windows.get_mut(id) {
-                    // Remove moved window from its current dock
-                    if let Some(current_dock) = window.current_dock {
-                        window.last_dock = current_dock;
-                        find_and_delete(&mut self.dock_windows[current_dock as usize], &id);
-                    }
-
-                    // Add to new dock and ensure active
-                    window.current_dock = Some(dock);
-                    self.dock_windows[dock as usize].push(id);
+                if self.windows.contains_key(&id) {
+                    self.place_window(id, WindowTarget::Dock(dock));
+                    self.constrain_docks(&ctx.props().options, None);
                     true
                 } else { false }
             }
             ToggleWindow(id) => {
-                if let Some(window) = self.windows.get_mut(id) {
+                if let Some(window) = self.windows.get(&id) {
+                    // Back from a popup
+                    if window.popup.is_some() {
+                        self.pop_in(id);
+                        self.constrain_docks(&ctx.props().options, None);
+                    }
+
                     // Hide
-                    if let Some(current_dock) = window.current_dock {
-                        window.current_dock = None;
-                        window.last_dock = current_dock;
-                        find_and_delete(&mut self.dock_windows[current_dock as usize], &id);
+                    else if window.current_dock.is_some() {
+                        self.dock_remove(id);
+                        if self.focused == Some(id) {
+                            self.focused = None;
+                        }
                     }
 
                     // Show
                     else {
-                        window.current_dock = Some(window.last_dock);
-                        self.dock_windows[window.last_dock as usize].push(id);
+                        self.dock_insert(id, window.last_dock);
+                        self.constrain_docks(&ctx.props().options, None);
+                        self.focus_window(id);
                     }
                     true
                 } else { false }
@@ -144,138 +445,793 @@ impl Component for Screen {
                     Right  => -dx,
                     Center => return false,
                 };
-                self.dock_sizes[dock as usize] = max(0, self.dock_sizes[dock as usize] + d);
+                let raw = match self.dock_drag {
+                    Some((dragged, raw)) if dragged == dock => raw,
+                    _ => self.effective_dock_size(dock),
+                };
+                let raw = (raw + d as f64).max(0.0);
+                if let Some((dragged, dragged_raw)) = self.dock_drag.as_mut() {
+                    if *dragged == dock {
+                        *dragged_raw = raw;
+                    }
+                }
+
+                // Only snap while dragging, the arrow keys would get stuck at a snap point
+                let options = &ctx.props().options;
+                self.dock_collapsed[dock as usize] = false;
+                self.dock_sizes[dock as usize] = match self.dock_drag {
+                    Some((dragged, _)) if dragged == dock => self.snap_dock_size(options, dock, raw),
+                    _ => raw,
+                };
+                self.constrain_docks(options, Some(dock));
+                self.dock_preferred_sizes[dock as usize] = self.dock_sizes[dock as usize];
+
+                // Rendering is deferred until the drag ends
+                self.apply_dock_sizes();
+                self.dock_drag.is_none()
+            }
+            BeginResizeDock(dock) => {
+                self.dock_drag = Some((dock, self.effective_dock_size(dock)));
+                self.dock_drag_start = (self.dock_sizes, self.dock_collapsed);
+                false
+            }
+            EndResizeDock => {
+                self.dock_drag.take().is_some()
+            }
+            CancelResizeDock => {
+                let dock = match self.dock_drag.take() {
+                    Some((dock, _)) => dock,
+                    None => return false,
+                };
+                (self.dock_sizes, self.dock_collapsed) = self.dock_drag_start;
+                self.constrain_docks(&ctx.props().options, None);
+                self.dock_preferred_sizes[dock as usize] = self.dock_sizes[dock as usize];
+                true
+            }
+            ToggleCollapseDock(dock) => {
+                if dock == DockPosition::Center {
+                    return false;
+                }
+                if self.effective_dock_size(dock) > 0.0 {
+                    self.dock_collapsed[dock as usize] = true;
+                } else {
+                    // Fall back to the initial size if the dock has been dragged to zero
+                    self.dock_collapsed[dock as usize] = false;
+                    if self.dock_sizes[dock as usize] < 1.0 {
+                        let (width, height) = self.docks_size();
+                        self.dock_sizes[dock as usize] = default_dock_size(dock, width, height);
+                    }
+                    self.constrain_docks(&ctx.props().options, Some(dock));
+                    self.dock_preferred_sizes[dock as usize] = self.dock_sizes[dock as usize];
+                }
+                true
+            }
+            SetDockMode(dock, mode) => {
+                if dock == DockPosition::Center || self.dock_modes[dock as usize] == mode {
+                    return false;
+                }
+                self.dock_modes[dock as usize] = mode;
+                true
+            }
+            SelectTab(id) => {
+                match self.windows.get(&id).and_then(|window| window.current_dock) {
+                    Some(DockPosition::Center) | None => false,
+                    Some(dock) => {
+                        self.dock_tabs[dock as usize] = Some(id);
+                        true
+                    }
+                }
+            }
+            ToggleCollapsed(id) => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.collapsed = !window.collapsed;
+                    true
+                } else { false }
+            }
+            DropWindow(dragged, target) => {
+                self.tile_hover = None;
+                if dragged.screen == self.id {
+                    if self.windows.contains_key(&dragged.window) {
+                        self.place_window(dragged.window, target);
+                        self.constrain_docks(&ctx.props().options, None);
+                    }
+                } else if let Some(source) = SCREENS.with(|screens| screens.borrow().get(&dragged.screen).cloned()) {
+                    source.send_message(TransferWindow(dragged.window, ctx.link().clone(), Some(target)));
+                } else {
+                    // Dragged in from another tab or browser window
+                    return false;
+                }
                 true
             }
+            TransferWindow(id, target_screen, target) => {
+                if let Some(window) = self.windows.get(&id) {
+                    // Keep the window visible if it was
+                    let target = target
+                        .or_else(|| window.current_dock.map(WindowTarget::Dock))
+                        .or_else(|| window.popup.as_ref().map(|_| WindowTarget::Dock(window.last_dock)));
+
+                    // The popup's close listener only knows this screen
+                    if let Some(popup) = self.windows.get_mut(&id).and_then(|window| window.popup.take()) {
+                        popup.close(&self.windows[&id].div);
+                    }
+                    self.dock_remove(id);
+                    let window = self.windows.remove(&id).expect("Checked above");
+                    self.window_drags.remove(&id);
+                    if matches!(self.dock_selector, Some((selected, _, _)) if selected == id) {
+                        self.dock_selector = None;
+                    }
+                    if self.focused == Some(id) {
+                        self.focused = None;
+                    }
+
+                    target_screen.send_message(AdoptWindow(window, target));
+                    true
+                } else { false }
+            }
+            PopOut(id) => {
+                let window = match self.windows.get(&id) {
+                    Some(window) if window.popup.is_none() => window,
+                    _ => return false,
+                };
+                let scope = ctx.link().clone();
+                let popup = Popup::open(&window.title, &window.div, move |_| {
+                    scope.send_message(ScreenMsg::PopIn(id));
+                });
+                if let Some(popup) = popup {
+                    self.dock_remove(id);
+                    if self.focused == Some(id) {
+                        self.focused = None;
+                    }
+                    if matches!(self.dock_selector, Some((selected, _, _)) if selected == id) {
+                        self.dock_selector = None;
+                    }
+                    self.windows.get_mut(&id).expect("Checked above").popup = Some(popup);
+                    true
+                } else {
+                    gloo::console::error!("Couldn't open a popup, it might have been blocked");
+                    false
+                }
+            }
+            PopIn(id) => {
+                if self.pop_in(id) {
+                    self.constrain_docks(&ctx.props().options, None);
+                    true
+                } else { false }
+            }
+            SetAutoHide(dock, auto_hide) => {
+                if dock == DockPosition::Center || self.dock_auto_hide[dock as usize] == auto_hide {
+                    return false;
+                }
+                self.dock_auto_hide[dock as usize] = auto_hide;
+                if self.dock_revealed == Some(dock) {
+                    self.dock_revealed = None;
+                }
+                self.constrain_docks(&ctx.props().options, None);
+                true
+            }
+            RevealDock(dock) => {
+                if self.dock_revealed != dock {
+                    self.dock_revealed = dock;
+                    true
+                } else { false }
+            }
+            Maximize(id) => {
+                match self.windows.get(&id) {
+                    // Hidden windows are only rendered maximized and stay hidden after `Restore`
+                    Some(window) if window.popup.is_none() => {
+                        self.maximized = Some(id);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Restore => self.maximized.take().is_some(),
+            Fullscreen(id) => {
+                if let Some(window) = self.windows.get(&id) {
+                    if let Err(error) = window.div.request_fullscreen() {
+                        gloo::console::error!("Couldn't enter fullscreen:", error);
+                    }
+                }
+                false
+            }
+            FocusWindow(id) => {
+                self.focus_window(id);
+                self.constrain_docks(&ctx.props().options, None);
+                true
+            }
+            TrackFocus(id) => {
+                ACTIVE_SCREEN.with(|active| active.set(Some(self.id)));
+                if self.focused != Some(id) && self.windows.contains_key(&id) {
+                    self.focused = Some(id);
+                    true
+                } else { false }
+            }
+            KeyDown(event) => {
+                if !self.receives_shortcuts(ctx, event.target()) {
+                    return false;
+                }
+                let action = match self.keymap.lookup(&event) {
+                    Some(action) => action,
+                    None => return false,
+                };
+                event.prevent_default();
+
+                let focused = self.focused.filter(|id| self.windows.contains_key(id));
+                match action {
+                    KeyAction::CycleFocus => self.cycle_focus(true),
+                    KeyAction::CycleFocusBackwards => self.cycle_focus(false),
+                    KeyAction::ToggleWindow(n) => match n.checked_sub(1).and_then(|n| self.windows.keys().nth(n)) {
+                        Some(&id) => self.update(ctx, ToggleWindow(id)),
+                        None => false,
+                    },
+                    KeyAction::MoveFocused(dock) => match focused {
+                        Some(id) => self.update(ctx, MoveWindow(id, *dock)),
+                        None => false,
+                    },
+                    KeyAction::ToggleMaximize => match (self.maximized, focused) {
+                        (Some(_), _) => self.update(ctx, Restore),
+                        (None, Some(id)) => self.update(ctx, Maximize(id)),
+                        (None, None) => false,
+                    },
+                    KeyAction::Custom(function) => {
+                        let div = focused
+                            .map(|id| JsValue::from(self.windows[&id].div.clone()))
+                            .unwrap_or(JsValue::UNDEFINED);
+                        if let Err(error) = function.call1(&JsValue::NULL, &div) {
+                            gloo::console::error!("Key binding threw:", error);
+                        }
+                        false
+                    }
+                }
+            }
+            BindKey(combo, action) => {
+                self.keymap.bind(combo, action);
+                false
+            }
+            AdoptWindow(window, target) => {
+                let id = self.next_window;
+                self.next_window += 1;
+                self.windows.insert(id, window);
+                self.track_window_drag(id);
+                if let Some(target) = target {
+                    self.place_window(id, target);
+                    self.constrain_docks(&ctx.props().options, None);
+                }
+                true
+            }
+            HoverTile(hover) => {
+                if self.tile_hover != hover {
+                    self.tile_hover = hover;
+                    true
+                } else { false }
+            }
+            LeaveTile(leaf) => {
+                // Another leaf might already be hovered
+                if self.tile_hover.is_some_and(|(hovered, _)| hovered == leaf) {
+                    self.tile_hover = None;
+                    true
+                } else { false }
+            }
+            DropOnTile(dragged, leaf) => {
+                let edge = self.tile_hover
+                    .filter(|(hovered, _)| *hovered == leaf)
+                    .map_or(DockPosition::Center, |(_, edge)| edge);
+                self.update(ctx, DropWindow(dragged, WindowTarget::Tile(leaf, edge)))
+            }
+            DropIntoWindow(id, payload) => {
+                if let Some(window) = self.windows.get(&id) {
+                    if let Some(on_drop) = window.on_drop.as_ref() {
+                        if let Err(error) = on_drop.call2(&JsValue::NULL, &payload.data(), &window.div) {
+                            gloo::console::error!("Drop handler threw:", error);
+                        }
+                    }
+                }
+                self.update(ctx, FocusWindow(id))
+            }
+            BeginResizeTile(split) => {
+                self.tile_drag = Some(split);
+                false
+            }
+            ResizeTile(split, dx, dy) => {
+                let center = match self.center_ref.cast::<HtmlElement>() {
+                    Some(center) => center,
+                    None => return false,
+                };
+                let width = center.offset_width() as f64;
+                let height = center.offset_height() as f64;
+                if !self.tiles.resize(split, dx, dy, width, height) {
+                    return false;
+                }
+
+                // Rendering is deferred until the drag ends
+                if self.tile_drag != Some(split) {
+                    return true;
+                }
+                let element = center.query_selector(&format!(".waw-split[data-split=\"{}\"]", split)).ok()
+                    .flatten()
+                    .and_then(|element| element.dyn_into::<HtmlElement>().ok());
+                if let (Some(element), Some(ratio)) = (element, self.tiles.ratio(split)) {
+                    let _ = element.style().set_property("--ratio", &ratio.to_string());
+                }
+                false
+            }
+            EndResizeTile => {
+                self.tile_drag.take().is_some()
+            }
             OpenSelector(id, x, y) => {
+                let (x, y) = self.client_to_local(x, y);
                 self.dock_selector = Some((id, x, y));
+                self.selector_focus = self.windows.get(&id)
+                    .map(|window| window.current_dock.unwrap_or(window.last_dock))
+                    .unwrap_or(DockPosition::Center);
                 true
             }
             CloseSelector(dock) => {
-                if let Some((_id, _, _)) = self.dock_selector.take() {
-                    if let Some(_dock) = dock {
-                        // currently closing the dock with a new dock is handled via MoveWindow
-                        panic!("Not Implemented");
+                if let Some((id, _, _)) = self.dock_selector.take() {
+                    self.focus_taskbar = Some(id);
+                    if let Some(dock) = dock {
+                        if self.windows.contains_key(&id) {
+                            self.place_window(id, WindowTarget::Dock(dock));
+                            self.constrain_docks(&ctx.props().options, None);
+                        }
                     }
                     true
                 } else { false }
             }
+            FocusSelector(dock) => {
+                if self.selector_focus != dock {
+                    self.selector_focus = dock;
+                    true
+                } else { false }
+            }
         }
     }
 
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        let mut dock_sizes = self.dock_sizes;
-        let docks: [Html; 4] = DockPosition::array()
-            .map(|dock| {
-                let (visible, html) = self.view_dock(ctx, dock);
-                if !visible {
-                    dock_sizes[dock as usize] = 0;
-                }
-                html
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        self.apply_dock_sizes();
+
+        if self.reported_focus != self.focused {
+            self.reported_focus = self.focused;
+            let div = self.focused
+                .and_then(|id| self.windows.get(&id))
+                .map(|window| JsValue::from(window.div.clone()))
+                .unwrap_or(JsValue::NULL);
+            let init = CustomEventInit::new();
+            init.set_detail(&div);
+            if let Ok(event) = CustomEvent::new_with_event_init_dict("focuschange", &init) {
+                let _ = ctx.props().parent.dispatch_event(&event);
+            }
+        }
+
+        // Move keyboard focus into the freshly opened selector
+        if let Some(selector) = self.selector_ref.cast::<HtmlElement>() {
+            let active = gloo::utils::document().active_element();
+            if !active.is_some_and(|active| selector.contains(Some(&active))) {
+                let _ = selector.focus();
+            }
+        }
+
+        // Give the focused window the DOM's focus
+        if self.focus_pending {
+            self.focus_pending = false;
+            let frame = self.focused
+                .zip(self.screen_ref.cast::<Element>())
+                .and_then(|(id, screen)| screen.query_selector(&format!(".waw-window[data-window=\"{}\"]", id)).ok().flatten())
+                .and_then(|frame| frame.dyn_into::<HtmlElement>().ok());
+            if let Some(frame) = frame {
+                let _ = frame.focus();
+            }
+        }
+
+        // Return keyboard focus to the taskbar after the selector closed
+        if let Some(id) = self.focus_taskbar.take() {
+            let entry = self.screen_ref.cast::<Element>()
+                .and_then(|screen| screen.query_selector(&format!(".waw-taskbar [data-window=\"{}\"]", id)).ok().flatten())
+                .and_then(|entry| entry.dyn_into::<HtmlElement>().ok());
+            if let Some(entry) = entry {
+                let _ = entry.focus();
+            }
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        SCREENS.with(|screens| {
+            let mut screens = screens.borrow_mut();
+            screens.remove(&self.id);
+            ACTIVE_SCREEN.with(|active| if active.get() == Some(self.id) {
+                active.set(screens.keys().next().copied());
             });
-        let center_dock = self.dock_windows[DockPosition::Center as usize]
-            .last()
-            .map(|&id| (id, &self.windows[id]))
-            .map(|(id, window)| self.view_window(ctx, id, window));
+        });
+        for window in self.windows.values_mut() {
+            if let Some(popup) = window.popup.take() {
+                popup.close(&window.div);
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        // The dock sizes are applied in `rendered`
+        let [top, left, bottom, right] = DockPosition::array()
+            .map(|dock| self.view_dock(ctx, dock));
 
-        let [top, left, bottom, right] = docks;
         return html!{
-            <div class="waw-screen">
+            <div
+                class="waw-screen"
+                ref={self.screen_ref.clone()}
+                ondragover={prevent_foreign_drop}
+                ondrop={prevent_foreign_drop}
+            >
                 {self.view_taskbar(ctx)}
-                <div class="waw-docks" style={
-                    format!("--top: {}px; --left: {}px; --bottom: {}px; --right: {}px;",
-                    dock_sizes[0], dock_sizes[1], dock_sizes[2], dock_sizes[3])
-                }>
+                <div class="waw-docks" ref={self.docks_ref.clone()}>
                     <div
                         class="waw-center-dock"
-                        ondragenter={self.make_drop_target.clone()}
-                        ondragover={self.make_drop_target.clone()}
-                        ondrop={ctx.link().batch_callback(|event: DragEvent| {
-                            let dt = event.data_transfer()?;
-                            let id = dt.get_data("application/waw").ok()?;
-                            let id: usize = id.parse().ok()?;
-                            event.prevent_default();
-                            Some(ScreenMsg::MoveWindow(id, DockPosition::Center))
-                        })}
+                        role="region"
+                        aria-label={DockPosition::Center.label()}
+                        ref={self.center_ref.clone()}
+                        onmousedown={ctx.link().callback(|_: MouseEvent| ScreenMsg::RevealDock(None))}
                     >
-                        if let Some(center) = center_dock {
-                            {center}
-                        }
+                        {self.view_tile(ctx, &self.tiles.root)}
                     </div>
                     {left}
                     {right}
                     {top}
                     {bottom}
+                    if let Some(id) = self.maximized {
+                        <div class="waw-maximized">
+                            {self.view_window_frame(ctx, id, &self.windows[&id])}
+                        </div>
+                    }
                 </div>
+                if let Some(selector) = self.view_dock_selector(ctx) {
+                    {selector}
+                }
             </div>
         };
     }
 }
+impl Screen {
+    /// Let a window's taskbar entry be dragged with pointer events
+    fn track_window_drag(&mut self, id: usize) {
+        let payload = DragPayload::Window(DraggedWindow { screen: self.id, window: id });
+        self.window_drags.insert(id, PayloadDragHandler::new(payload).into_callbacks());
+    }
+
+    /// Find the id of a window by its `<div>`
+    pub fn find_window(&self, div: &Element) -> Option<usize> {
+        self.windows.iter()
+            .find(|(_, window)| &window.div == div)
+            .map(|(&id, _)| id)
+    }
+
+    /// Move a window from wherever it currently is to a new place
+    fn place_window(&mut self, id: usize, target: WindowTarget) {
+        // Take the div back before rendering it, the popup would stay open and empty otherwise
+        if let Some(popup) = self.windows.get_mut(&id).and_then(|window| window.popup.take()) {
+            popup.close(&self.windows[&id].div);
+        }
+        self.dock_remove(id);
+        match target {
+            WindowTarget::Dock(dock) => self.dock_insert(id, dock),
+            WindowTarget::Tile(leaf, edge) => {
+                if self.tiles.insert_at(leaf, edge, id) {
+                    let window = self.windows.get_mut(&id).expect("Checked by caller");
+                    window.current_dock = Some(DockPosition::Center);
+                    window.collapsed = false;
+                    self.dock_windows[DockPosition::Center as usize].push(id);
+                } else {
+                    // The leaf vanished while dragging
+                    self.dock_insert(id, DockPosition::Center);
+                }
+            }
+        }
+    }
+
+    /// Get a window's `<div>` if it has the focus
+    pub fn focused_window(&self) -> Option<Element> {
+        self.focused
+            .and_then(|id| self.windows.get(&id))
+            .map(|window| window.div.clone())
+    }
+
+    /// Make a window the target for keyboard shortcuts and ensure it can be seen
+    fn focus_window(&mut self, id: usize) {
+        let window = match self.windows.get_mut(&id) {
+            Some(window) if window.popup.is_none() => window,
+            _ => return,
+        };
+        window.collapsed = false;
+        let last_dock = window.last_dock;
+        match window.current_dock {
+            None => self.dock_insert(id, last_dock),
+            Some(DockPosition::Center) => {
+                self.tiles.raise(id);
+            }
+            Some(dock) => {
+                self.dock_tabs[dock as usize] = Some(id);
+                if self.dock_auto_hide[dock as usize] {
+                    self.dock_revealed = Some(dock);
+                }
+            }
+        }
+        self.focused = Some(id);
+        self.focus_pending = true;
+        ACTIVE_SCREEN.with(|active| active.set(Some(self.id)));
+    }
+
+    /// Get the window a tabbed dock shows
+    fn active_tab(&self, dock: DockPosition) -> Option<usize> {
+        // Fall back to the last window if the selected one left the dock
+        let dock_windows = &self.dock_windows[dock as usize];
+        self.dock_tabs[dock as usize]
+            .filter(|id| dock_windows.contains(id))
+            .or_else(|| dock_windows.last().copied())
+    }
+
+    /// Check whether a window's content is rendered and not hidden behind others in its dock
+    fn is_rendered(&self, id: usize) -> bool {
+        let window = match self.windows.get(&id) {
+            Some(window) => window,
+            None => return false,
+        };
+        match window.current_dock {
+            _ if self.maximized == Some(id) => true,
+            None => false,
+            Some(DockPosition::Center) => self.tiles.is_visible(id),
+            Some(dock) => match self.dock_modes[dock as usize] {
+                DockMode::Stack => true,
+                DockMode::Tabs => self.active_tab(dock) == Some(id),
+                DockMode::Accordion => !window.collapsed,
+            },
+        }
+    }
+
+    /// Focus the next or previous window which can currently be seen
+    fn cycle_focus(&mut self, forwards: bool) -> bool {
+        let mut visible: Vec<usize> = self.windows.keys()
+            .copied()
+            .filter(|&id| self.is_rendered(id))
+            .collect();
+        if !forwards {
+            visible.reverse();
+        }
+        let next = match self.focused.and_then(|focused| visible.iter().position(|&id| id == focused)) {
+            Some(index) => visible.get(index + 1).or_else(|| visible.first()),
+            None => visible.first(),
+        };
+        match next {
+            Some(&id) => {
+                self.focus_window(id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether a keydown's target should trigger shortcuts
+    ///
+    /// Text inputs keep their keys and other screens' content is ignored.
+    /// Keys without a focused element only go to the screen last interacted with.
+    fn receives_shortcuts(&self, ctx: &Context<Self>, target: Option<EventTarget>) -> bool {
+        let target = match target.and_then(|target| target.dyn_into::<HtmlElement>().ok()) {
+            Some(target) => target,
+            None => return ACTIVE_SCREEN.with(|active| active.get() == Some(self.id)),
+        };
+        if target.is_content_editable()
+            || matches!(target.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT") {
+            return false;
+        }
+        let body = gloo::utils::document().body();
+        if body.as_ref() == Some(&target) {
+            return ACTIVE_SCREEN.with(|active| active.get() == Some(self.id));
+        }
+        ctx.props().parent.contains(Some(&target))
+    }
+
+    /// Close a window's popup and re-dock it into its last dock
+    ///
+    /// Returns `false` if the window wasn't popped out.
+    /// Docked windows are left alone, closing the popup would remove their div.
+    fn pop_in(&mut self, id: usize) -> bool {
+        let window = match self.windows.get_mut(&id) {
+            Some(window) if window.current_dock.is_none() => window,
+            _ => return false,
+        };
+        match window.popup.take() {
+            Some(popup) => {
+                popup.close(&window.div);
+                let dock = window.last_dock;
+                self.dock_insert(id, dock);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a window from the dock it is currently shown in
+    fn dock_remove(&mut self, id: usize) {
+        let window = self.windows.get_mut(&id).expect("Checked by caller");
+        if self.maximized == Some(id) {
+            self.maximized = None;
+        }
+        if let Some(current_dock) = window.current_dock.take() {
+            window.last_dock = current_dock;
+            find_and_delete(&mut self.dock_windows[current_dock as usize], &id);
+            if current_dock == DockPosition::Center {
+                self.tiles.remove(id);
+            }
+        }
+    }
+
+    /// Convert viewport coordinates into coordinates relative to the screen's top left corner
+    fn client_to_local(&self, x: i32, y: i32) -> (i32, i32) {
+        match self.screen_ref.cast::<Element>() {
+            Some(screen) => {
+                let rect = screen.get_bounding_client_rect();
+                (x - rect.x().floor() as i32, y - rect.y().floor() as i32)
+            }
+            None => (x, y),
+        }
+    }
+
+    /// Size of the area shared by all docks
+    fn docks_size(&self) -> (f64, f64) {
+        match self.docks_ref.cast::<HtmlElement>() {
+            Some(docks) => (docks.offset_width() as f64, docks.offset_height() as f64),
+            None => (self.width as f64, self.height as f64),
+        }
+    }
+
+    /// Write the docks' sizes into the css variables of `.waw-docks`
+    ///
+    /// Resizing a dock only touches these variables instead of rendering the whole screen again.
+    fn apply_dock_sizes(&self) {
+        let docks = match self.docks_ref.cast::<HtmlElement>() {
+            Some(docks) => docks,
+            None => return,
+        };
+        let style = docks.style();
+        for dock in DockPosition::array() {
+            let name = match dock {
+                DockPosition::Top    => "top",
+                DockPosition::Left   => "left",
+                DockPosition::Bottom => "bottom",
+                DockPosition::Right  => "right",
+                DockPosition::Center => unreachable!(),
+            };
+            let size = self.effective_dock_size(dock);
+            // Hidden docks and those sliding out over the center don't take space in the grid
+            let visible = !self.dock_windows[dock as usize].is_empty();
+            let grid_size = if visible && !self.dock_auto_hide[dock as usize] { size } else { 0.0 };
+            let _ = style.set_property(&format!("--{}", name), &format!("{}px", grid_size));
+            let _ = style.set_property(&format!("--{}-size", name), &format!("{}px", size));
+        }
+    }
+
+    /// Size of a dock as shown, ignoring whether it has any windows
+    fn effective_dock_size(&self, dock: DockPosition) -> f64 {
+        if self.dock_collapsed[dock as usize] {
+            0.0
+        } else {
+            self.dock_sizes[dock as usize]
+        }
+    }
+
+    /// Snap a dock's size to the closest snap point in reach
+    fn snap_dock_size(&self, options: &ScreenOptions, dock: DockPosition, size: f64) -> f64 {
+        let (width, height) = self.docks_size();
+        let dimension = if matches!(dock, DockPosition::Top | DockPosition::Bottom) { height } else { width };
+        options.snap_points.iter()
+            .map(|point| point * dimension)
+            .filter(|point| (point - size).abs() <= options.snap_distance)
+            .min_by(|a, b| (a - size).abs().total_cmp(&(b - size).abs()))
+            .unwrap_or(size)
+    }
+
+    /// Apply the min and max dock sizes and ensure the center's min size
+    ///
+    /// If a single dock has just been resized by the user, it will give up
+    /// the space needed by the center instead of its opposite.
+    fn constrain_docks(&mut self, options: &ScreenOptions, resized: Option<DockPosition>) {
+        // Hidden docks don't take any space
+        let visible = DockPosition::array().map(|dock| !self.dock_windows[dock as usize].is_empty()
+            && !self.dock_auto_hide[dock as usize]
+            && !self.dock_collapsed[dock as usize]);
+        let (width, height) = self.docks_size();
+        constrain_dock_sizes(&mut self.dock_sizes, visible, width, height, options, resized);
+    }
+
+    /// Show a hidden window in a dock
+    ///
+    /// Does nothing if the window is already shown somewhere.
+    fn dock_insert(&mut self, id: usize, dock: DockPosition) {
+        let window = self.windows.get_mut(&id).expect("Checked by caller");
+        if window.current_dock.is_some() {
+            return;
+        }
+        window.current_dock = Some(dock);
+        window.collapsed = false;
+        self.dock_windows[dock as usize].push(id);
+        if dock == DockPosition::Center {
+            self.tiles.insert(id);
+        } else {
+            self.dock_tabs[dock as usize] = Some(id);
+        }
+    }
+}
 impl Screen {
     fn view_taskbar(&self, ctx: &Context<Self>) -> Html {
+        let screen = self.id;
         let windows = self.windows.iter()
-            .enumerate()
-            .map(|(id, window)| {
-                let open = window.current_dock.is_some();
+            .map(|(&id, window)| {
+                let open = window.current_dock.is_some() || window.popup.is_some();
                 let menu_open = matches!(self.dock_selector, Some((s_id, _, _)) if s_id == id);
+                let focused = self.focused == Some(id);
                 return html!{
-                    <div>
-                        if open {
-                            <div class="waw-open-indicator"/>
-                        } else {
-                            <div/>
-                        }
-                        <img
-                            src={window.icon.clone()}
-                            alt={window.title.clone()}
+                    <div class={focused.then_some("waw-focused")}>
+                        <div class={open.then_some("waw-open-indicator")} aria-hidden="true"/>
+                        // Firefox doesn't start drags on buttons, so the toggle only acts like one
+                        <div
+                            class="waw-taskbar-toggle"
+                            role="button"
+                            tabindex="0"
+                            title={window.title.clone()}
+                            aria-pressed={open.to_string()}
                             draggable="true"
                             ondragstart={Callback::from(move |event: DragEvent| {
                                 if let Some(dt) = event.data_transfer() {
-                                    dt.set_data("application/waw", &id.to_string()).unwrap();
+                                    DragPayload::Window(DraggedWindow { screen, window: id }).write(&dt);
                                 }
                             })}
+                            onpointerdown={self.window_drags.get(&id).map(|drag| {
+                                // Mice and pens use html's drag and drop
+                                let pointer = drag.pointer.clone();
+                                Callback::from(move |event: PointerEvent| {
+                                    if event.pointer_type() == "touch" {
+                                        pointer.emit(event);
+                                    }
+                                })
+                            })}
+                            onkeydown={ctx.link().batch_callback(move |event: KeyboardEvent| activate_key(&event, ScreenMsg::ToggleWindow(id)))}
                             onclick={ctx.link().callback(move |_: MouseEvent| {
                                 ScreenMsg::ToggleWindow(id)
                             })}
-                        />
-                        if !menu_open {
-                            <div
-                                onclick={ctx.link().callback(move |event: MouseEvent| {
-                                    let target: HtmlElement = event.target()
-                                        .expect("It's a div, see two lines above")
-                                        .dyn_into()
-                                        .expect("It's a div, see two lines above");
-                                    let rect = target.get_bounding_client_rect();
-                                    let x = rect.x() + rect.width() / 2.0;
-                                    let y = rect.y() + rect.height() / 2.0;
-                                    ScreenMsg::OpenSelector(id, x.floor() as i32, y.floor() as i32)
-                                })}
-                            />
-                        } else {
-                            <div
-                                onclick={ctx.link().callback(move |_: MouseEvent| {
+                        >
+                            <img src={window.icon.clone()} alt={window.title.clone()} draggable="false"/>
+                        </div>
+                        <button
+                            class="waw-taskbar-menu"
+                            type="button"
+                            data-window={id.to_string()}
+                            aria-label={format!("Move {}", window.title)}
+                            aria-haspopup="menu"
+                            aria-expanded={menu_open.to_string()}
+                            onclick={ctx.link().callback(move |event: MouseEvent| {
+                                if menu_open {
                                     ScreenMsg::CloseSelector(None)
-                                })}
-                            />
-                        }
-                        if menu_open {
-                            <div>
-                                {self.view_dock_selector(ctx).unwrap()}
-                            </div>
-                        }
+                                } else {
+                                    open_selector(id, &event)
+                                }
+                            })}
+                        />
                     </div>
                 };
             });
 
         return html!{
-            <div class="waw-taskbar">
+            <div
+                class="waw-taskbar"
+                role="toolbar"
+                aria-label="Windows"
+                onkeydown={|event: KeyboardEvent| { focus_toolbar_button(&event); }}
+            >
                 {for windows}
             </div>
         };
     }
 
-    fn view_dock(&self, ctx: &Context<Self>, dock: DockPosition) -> (bool, Html) {
+    fn view_dock(&self, ctx: &Context<Self>, dock: DockPosition) -> Html {
         use DockPosition::*;
+        let options = &ctx.props().options;
         let dock_class = match dock {
             Top    => "waw-top-dock",
             Left   => "waw-left-dock",
@@ -291,82 +1247,358 @@ impl Screen {
             Center => unreachable!(),
         };
 
-        let windows: Vec<Html> = self.dock_windows[dock as usize]
-            .iter()
-            .map(|&id| (id, &self.windows[id]))
-            .map(|(id, window)| html!{
-                <key={id}>
-                    {self.view_window(ctx, id, window)}
-                </>
-            })
-            .collect();
+        let dock_windows = &self.dock_windows[dock as usize];
+        let mode = self.dock_modes[dock as usize];
+        let mode_class = match mode {
+            DockMode::Stack     => "waw-stack",
+            DockMode::Tabs      => "waw-tabs",
+            DockMode::Accordion => "waw-accordion",
+        };
+
+        let visible = !dock_windows.is_empty();
+        let auto_hide = self.dock_auto_hide[dock as usize];
+        let revealed = auto_hide && self.dock_revealed == Some(dock);
 
-        let visible = windows.len() > 0;
-        return (visible, html!{
+        let (vertical, extent) = match dock {
+            Left | Right => (true, self.width),
+            _            => (false, self.height),
+        };
+        let anchor = html!{
+            <Anchor class={anchor_class}
+                label={format!("Resize {}", dock.label().to_lowercase())}
+                vertical={vertical}
+                value={self.effective_dock_size(dock)}
+                max={extent as f64}
+                step={options.resize_step}
+                large_step={options.resize_large_step}
+                ghost={options.ghost_resize}
+                on_begin={ctx.link().callback(move |_| ScreenMsg::BeginResizeDock(dock))}
+                on_move={ctx.link().callback(move |(dx, dy)|
+                    ScreenMsg::ResizeDock(dock, dx, dy)
+                )}
+                on_end={ctx.link().callback(|_| ScreenMsg::EndResizeDock)}
+                on_cancel={ctx.link().callback(|_| ScreenMsg::CancelResizeDock)}
+                on_double_click={ctx.link().callback(move |_| ScreenMsg::ToggleCollapseDock(dock))}
+            />
+        };
+        let pin = html!{
+            <button
+                class={classes!("waw-pin", auto_hide.then_some("waw-unpinned"))}
+                title={if auto_hide { "Pin" } else { "Auto-hide" }}
+                aria-label={if auto_hide { "Pin" } else { "Auto-hide" }}
+                onclick={ctx.link().callback(move |_: MouseEvent| ScreenMsg::SetAutoHide(dock, !auto_hide))}
+            />
+        };
+        let on_drop = ctx.link().batch_callback(move |payload: DragPayload| {
+            Some(ScreenMsg::DropWindow(payload.window()?, WindowTarget::Dock(dock)))
+        });
+
+        return html!{
             <div
-                class={dock_class}
-                ondragenter={self.make_drop_target.clone()}
-                ondragover={self.make_drop_target.clone()}
-                ondrop={ctx.link().batch_callback(move |event: DragEvent| {
-                    let dt = event.data_transfer()?;
-                    let id = dt.get_data("application/waw").ok()?;
-                    let id: usize = id.parse().ok()?;
-                    event.prevent_default();
-                    Some(ScreenMsg::MoveWindow(id, dock))
-                })}
+                class={classes!(dock_class, auto_hide.then_some("waw-auto-hide"), revealed.then_some("waw-revealed"))}
+                role="region"
+                aria-label={dock.label()}
+                onmouseleave={ctx.link().batch_callback(move |_: MouseEvent|
+                    auto_hide.then_some(ScreenMsg::RevealDock(None))
+                )}
             >
-                if visible {
-                    <Anchor class={anchor_class}
-                        on_move={ctx.link().callback(move |(dx, dy)|
-                            ScreenMsg::ResizeDock(dock, dx, dy)
-                        )}
+                if visible && auto_hide {
+                    <div
+                        class="waw-auto-hide-strip"
+                        role="button"
+                        aria-label={format!("Show {}", dock.label().to_lowercase())}
+                        onmouseenter={ctx.link().callback(move |_: MouseEvent| ScreenMsg::RevealDock(Some(dock)))}
+                        onclick={ctx.link().callback(move |_: MouseEvent| ScreenMsg::RevealDock(Some(dock)))}
                     />
-                    <div class="waw-container">
-                        {for windows.into_iter()}
-                    </div>
+                    <DropZone class={classes!("waw-container", mode_class)} over_class="waw-drop-over"
+                        accepts={self.window_drops.clone()} on_drop={on_drop}>
+                        {self.view_dock_content(ctx, dock, mode)}
+                        {anchor}
+                        {pin}
+                    </DropZone>
+                } else if visible {
+                    {anchor}
+                    <DropZone class={classes!("waw-container", mode_class)} over_class="waw-drop-over"
+                        accepts={self.window_drops.clone()} on_drop={on_drop}>
+                        {self.view_dock_content(ctx, dock, mode)}
+                        {pin}
+                    </DropZone>
                 } else {
-                    <DropZone class="waw-drop-zone" over_class="waw-active"/>
+                    <DropZone class="waw-drop-zone" over_class="waw-active"
+                        accepts={self.window_drops.clone()} on_drop={on_drop}/>
                 }
             </div>
-        });
+        };
+    }
+
+    fn view_dock_content(&self, ctx: &Context<Self>, dock: DockPosition, mode: DockMode) -> Html {
+        let dock_windows = &self.dock_windows[dock as usize];
+        match mode {
+            DockMode::Stack => html!{
+                for dock_windows.iter().map(|&id| html!{
+                    <key={id}>
+                        {self.view_window(ctx, id, &self.windows[&id])}
+                    </>
+                })
+            },
+            DockMode::Tabs => {
+                let active = match self.active_tab(dock) {
+                    Some(active) => active,
+                    None => return Html::default(),
+                };
+                let screen = self.id;
+                let tabs = dock_windows.iter().map(|&id| {
+                    let window = &self.windows[&id];
+                    html!{
+                        <div
+                            key={id}
+                            class={classes!("waw-tab", (id == active).then_some("waw-active"))}
+                            role="tab"
+                            tabindex="0"
+                            aria-selected={(id == active).to_string()}
+                            onkeydown={ctx.link().batch_callback(move |event: KeyboardEvent| activate_key(&event, ScreenMsg::SelectTab(id)))}
+                            onclick={ctx.link().callback(move |_: MouseEvent| ScreenMsg::SelectTab(id))}
+                            draggable="true"
+                            ondragstart={Callback::from(move |event: DragEvent| {
+                                if let Some(dt) = event.data_transfer() {
+                                    DragPayload::Tab(DraggedWindow { screen, window: id }).write(&dt);
+                                }
+                            })}
+                        >
+                            <img src={window.icon.clone()} alt="" draggable="false"/>
+                            {&window.title}
+                        </div>
+                    }
+                });
+                html!{
+                    <>
+                        <div class="waw-tab-bar" role="tablist" aria-label={dock.label()}>
+                            {for tabs}
+                        </div>
+                        {self.view_window(ctx, active, &self.windows[&active])}
+                    </>
+                }
+            }
+            DockMode::Accordion => html!{
+                for dock_windows.iter().map(|&id| {
+                    let window = &self.windows[&id];
+                    html!{
+                        <key={id}>
+                            <div
+                                class={classes!("waw-accordion-header", window.collapsed.then_some("waw-collapsed"))}
+                                role="button"
+                                tabindex="0"
+                                aria-expanded={(!window.collapsed).to_string()}
+                                onkeydown={ctx.link().batch_callback(move |event: KeyboardEvent| activate_key(&event, ScreenMsg::ToggleCollapsed(id)))}
+                                onclick={ctx.link().callback(move |_: MouseEvent| ScreenMsg::ToggleCollapsed(id))}
+                            >
+                                <img src={window.icon.clone()} alt=""/>
+                                {&window.title}
+                            </div>
+                            if !window.collapsed {
+                                {self.view_window(ctx, id, window)}
+                            }
+                        </>
+                    }
+                })
+            },
+        }
+    }
+
+    fn view_tile(&self, ctx: &Context<Self>, tile: &Tile) -> Html {
+        match tile {
+            Tile::Split { id, direction, ratio, first, second } => {
+                let id = *id;
+                let options = &ctx.props().options;
+                let (class, anchor_class) = match direction {
+                    SplitDirection::Horizontal => ("waw-horizontal", "waw-w"),
+                    SplitDirection::Vertical   => ("waw-vertical", "waw-n"),
+                };
+                html!{
+                    <div key={id} class={classes!("waw-split", class)} data-split={id.to_string()} style={format!("--ratio: {}", ratio)}>
+                        <div class="waw-tile">
+                            {self.view_tile(ctx, first)}
+                        </div>
+                        <div class="waw-tile">
+                            {self.view_tile(ctx, second)}
+                            <Anchor class={anchor_class}
+                                label="Resize split"
+                                vertical={*direction == SplitDirection::Horizontal}
+                                value={ratio * 100.0}
+                                step={options.resize_step}
+                                large_step={options.resize_large_step}
+                                ghost={options.ghost_resize}
+                                on_begin={ctx.link().callback(move |_| ScreenMsg::BeginResizeTile(id))}
+                                on_move={ctx.link().callback(move |(dx, dy)|
+                                    ScreenMsg::ResizeTile(id, dx, dy)
+                                )}
+                                on_end={ctx.link().callback(|_| ScreenMsg::EndResizeTile)}
+                            />
+                        </div>
+                    </div>
+                }
+            }
+            Tile::Leaf { id, windows } => {
+                let leaf = *id;
+                // Empty leaves are filled instead of split
+                let preview = self.tile_hover
+                    .filter(|(hovered, _)| *hovered == leaf)
+                    .map(|(_, edge)| if windows.is_empty() { DockPosition::Center } else { edge })
+                    .map(|edge| match edge {
+                        DockPosition::Top    => "waw-top",
+                        DockPosition::Left   => "waw-left",
+                        DockPosition::Bottom => "waw-bottom",
+                        DockPosition::Right  => "waw-right",
+                        DockPosition::Center => "waw-center",
+                    });
+                html!{
+                    <DropZone
+                        key={leaf}
+                        class="waw-leaf"
+                        accepts={self.window_drops.clone()}
+                        on_over={ctx.link().callback(move |over: DragOver|
+                            ScreenMsg::HoverTile(Some((leaf, drop_edge(over.x, over.y))))
+                        )}
+                        on_leave={ctx.link().callback(move |_| ScreenMsg::LeaveTile(leaf))}
+                        on_drop={ctx.link().batch_callback(move |payload: DragPayload| {
+                            Some(ScreenMsg::DropOnTile(payload.window()?, leaf))
+                        })}
+                    >
+                        if let Some(id) = TileTree::visible(windows) {
+                            {self.view_window(ctx, id, &self.windows[&id])}
+                        }
+                        if let Some(preview) = preview {
+                            <div class={classes!("waw-tile-preview", preview)}/>
+                        }
+                    </DropZone>
+                }
+            }
+        }
     }
 
     fn view_window(&self, ctx: &Context<Self>, id: usize, window: &Window) -> Html {
+        // A div can only be in one place, so leave a gap where the maximized window was
+        if self.maximized == Some(id) {
+            return html!{
+                <div class="waw-window waw-placeholder"/>
+            };
+        }
+        self.view_window_frame(ctx, id, window)
+    }
+
+    fn view_window_frame(&self, ctx: &Context<Self>, id: usize, window: &Window) -> Html {
+        let maximized = self.maximized == Some(id);
+        let toggle_maximize = move |_: MouseEvent| {
+            if maximized { ScreenMsg::Restore } else { ScreenMsg::Maximize(id) }
+        };
         return html!{
-            <div class="waw-window">
-                <img
-                    class="waw-window-icon"
-                    src={window.icon.clone()}
-                    alt={window.title.clone()}
-                    draggable="false"
-                    onclick={ctx.link().callback(move |_: MouseEvent| {
-                        ScreenMsg::ToggleWindow(id)
-                    })}
-                />
-                {Html::VRef(window.div.clone().into())}
+            <div
+                class={classes!("waw-window", (self.focused == Some(id)).then_some("waw-focused"))}
+                role="group"
+                aria-label={window.title.clone()}
+                tabindex="-1"
+                data-window={id.to_string()}
+                onpointerdown={ctx.link().callback(move |_: PointerEvent| ScreenMsg::TrackFocus(id))}
+                onfocusin={ctx.link().callback(move |_: FocusEvent| ScreenMsg::TrackFocus(id))}
+            >
+                <div class="waw-window-bar" ondblclick={ctx.link().callback(toggle_maximize)}>
+                    <img
+                        class="waw-window-icon"
+                        src={window.icon.clone()}
+                        alt={window.title.clone()}
+                        draggable="false"
+                        onclick={ctx.link().callback(move |_: MouseEvent| {
+                            ScreenMsg::ToggleWindow(id)
+                        })}
+                    />
+                    <span class="waw-window-title">{&window.title}</span>
+                    <div class="waw-window-actions" ondblclick={|event: MouseEvent| event.stop_propagation()}>
+                        <button
+                            class={if maximized { "waw-restore" } else { "waw-maximize" }}
+                            title={if maximized { "Restore" } else { "Maximize" }}
+                            aria-label={if maximized { "Restore" } else { "Maximize" }}
+                            onclick={ctx.link().callback(toggle_maximize)}
+                        />
+                        <button
+                            class="waw-fullscreen"
+                            title="Fullscreen"
+                            aria-label="Fullscreen"
+                            onclick={ctx.link().callback(move |_: MouseEvent| {
+                                ScreenMsg::Fullscreen(id)
+                            })}
+                        />
+                        <button
+                            class="waw-pop-out"
+                            title="Pop out"
+                            aria-label="Pop out"
+                            onclick={ctx.link().callback(move |_: MouseEvent| {
+                                ScreenMsg::PopOut(id)
+                            })}
+                        />
+                    </div>
+                </div>
+                <DropZone class="waw-window-content" over_class="waw-drop-over"
+                    accepts={window.accepts.clone()}
+                    on_drop={window.accepts.is_some().then(|| ctx.link().callback(move |payload| ScreenMsg::DropIntoWindow(id, payload)))}
+                >
+                    {Html::VRef(window.div.clone().into())}
+                </DropZone>
             </div>
         };
     }
 
     fn view_dock_selector(&self, ctx: &Context<Self>) -> Option<Html> {
         self.dock_selector.map(|(id, x, y)| {
-            let on_click = move |dock| {
-                ctx.link().callback(move |_: MouseEvent|
-                    ScreenMsg::MoveWindow(id, dock)
-                )
+            let focus = self.selector_focus;
+            let item_id = |dock: DockPosition| format!("waw-{}-selector-{}", self.id, dock.label().to_lowercase().replace(' ', "-"));
+            let target = |dock: DockPosition| {
+                html!{
+                    <div
+                        id={item_id(dock)}
+                        class={(dock == focus).then_some("waw-selected")}
+                        role="menuitem"
+                        aria-label={dock.label()}
+                        title={dock.label()}
+                        onclick={ctx.link().callback(move |_: MouseEvent|
+                            ScreenMsg::CloseSelector(Some(dock))
+                        )}
+                    />
+                }
             };
             return html!{
                 <div class="waw-modal-background" onclick={ctx.link().callback(|_: MouseEvent| {
                     ScreenMsg::CloseSelector(None)
                 })}>
-                    <div class="waw-dock-selector" style={
-                        format!("--x: {}px; --y: {}px", x, y)
-                    }>
-                        <div onclick={on_click(DockPosition::Top)}/>
-                        <div onclick={on_click(DockPosition::Left)}/>
-                        <div onclick={on_click(DockPosition::Bottom)}/>
-                        <div onclick={on_click(DockPosition::Right)}/>
-                        <div onclick={on_click(DockPosition::Center)}/>
+                    <div
+                        class="waw-dock-selector"
+                        ref={self.selector_ref.clone()}
+                        tabindex="-1"
+                        role="menu"
+                        aria-label={format!("Move {} to", self.windows[&id].title)}
+                        aria-activedescendant={item_id(focus)}
+                        style={format!("--x: {}px; --y: {}px", x, y)}
+                        onkeydown={ctx.link().batch_callback(move |event: KeyboardEvent| {
+                            use DockPosition::*;
+                            // Step towards a direction or back to the center from the opposite side
+                            let step = |direction, opposite| if focus == opposite { Center } else { direction };
+                            let msg = match event.key().as_str() {
+                                "ArrowUp"    => ScreenMsg::FocusSelector(step(Top, Bottom)),
+                                "ArrowLeft"  => ScreenMsg::FocusSelector(step(Left, Right)),
+                                "ArrowDown"  => ScreenMsg::FocusSelector(step(Bottom, Top)),
+                                "ArrowRight" => ScreenMsg::FocusSelector(step(Right, Left)),
+                                "Enter" | " " => ScreenMsg::CloseSelector(Some(focus)),
+                                "Escape" | "Tab" => ScreenMsg::CloseSelector(None),
+                                _ => return None,
+                            };
+                            event.prevent_default();
+                            Some(msg)
+                        })}
+                    >
+                        {target(DockPosition::Top)}
+                        {target(DockPosition::Left)}
+                        {target(DockPosition::Bottom)}
+                        {target(DockPosition::Right)}
+                        {target(DockPosition::Center)}
                     </div>
                 </div>
             };
@@ -374,6 +1606,181 @@ impl Screen {
     }
 }
 
+/// Open the dock selector below the taskbar entry an event originated from
+fn open_selector(id: usize, event: &Event) -> ScreenMsg {
+    let target: HtmlElement = event.target()
+        .expect("Only used for handlers on elements")
+        .dyn_into()
+        .expect("Only used for handlers on elements");
+    let rect = target.get_bounding_client_rect();
+    let x = rect.x() + rect.width() / 2.0;
+    let y = rect.y() + rect.height() / 2.0;
+    ScreenMsg::OpenSelector(id, x.floor() as i32, y.floor() as i32)
+}
+
+/// Trigger a message when a button-like element is activated by the keyboard
+fn activate_key(event: &KeyboardEvent, msg: ScreenMsg) -> Option<ScreenMsg> {
+    match event.key().as_str() {
+        "Enter" | " " => {
+            event.prevent_default();
+            Some(msg)
+        }
+        _ => None,
+    }
+}
+
+/// Move the focus between a toolbar's buttons using the arrow, home and end keys
+fn focus_toolbar_button(event: &KeyboardEvent) -> Option<()> {
+    let key = event.key();
+    if !matches!(key.as_str(), "ArrowRight" | "ArrowDown" | "ArrowLeft" | "ArrowUp" | "Home" | "End") {
+        return None;
+    }
+
+    // Yew delegates events, so `current_target` isn't the toolbar
+    let target: Element = event.target()?.dyn_into().ok()?;
+    let toolbar = target.closest("[role=toolbar]").ok()??;
+    let buttons = toolbar.query_selector_all("button, [role=button]").ok()?;
+    let buttons: Vec<HtmlElement> = (0..buttons.length())
+        .filter_map(|index| buttons.get(index)?.dyn_into().ok())
+        .collect();
+    let current = buttons.iter().position(|button| **button == target)?;
+    let next = match key.as_str() {
+        "ArrowRight" | "ArrowDown" => (current + 1) % buttons.len(),
+        "ArrowLeft" | "ArrowUp" => (current + buttons.len() - 1) % buttons.len(),
+        "Home" => 0,
+        _ => buttons.len() - 1,
+    };
+    event.prevent_default();
+    buttons[next].focus().ok()
+}
+
+/// Stop the browser from opening files or links nothing inside the screen accepted
+///
+/// Drop zones taking the payload already prevented the event, editable elements keep their text drops.
+fn prevent_foreign_drop(event: DragEvent) {
+    let foreign = event.data_transfer()
+        .and_then(|dt| PayloadKind::of(&dt))
+        .is_some_and(|kind| match kind {
+            PayloadKind::Files => true,
+            PayloadKind::Text => event.target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|target| target.closest("input, textarea, [contenteditable]").ok().flatten())
+                .is_none(),
+            _ => false,
+        });
+    if foreign && !event.default_prevented() {
+        event.prevent_default();
+        if let Some(dt) = event.data_transfer() {
+            dt.set_drop_effect("none");
+        }
+    }
+}
+
+/// Decide which edge of a leaf a window is dragged over
+///
+/// `x` and `y` are the pointer's position as fraction of the leaf's size.
+fn drop_edge(x: f64, y: f64) -> DockPosition {
+    use DockPosition::*;
+    // Pick the closest edge inside the outer quarter
+    [(y, Top), (x, Left), (1.0 - y, Bottom), (1.0 - x, Right)]
+        .into_iter()
+        .filter(|(distance, _)| *distance < 0.25)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map_or(Center, |(_, edge)| edge)
+}
+
+/// Apply the min and max dock sizes and ensure the center's min size
+///
+/// Sizes are left alone while there is no space at all, i.e. the screen is hidden.
+fn constrain_dock_sizes(sizes: &mut [f64; 4], visible: [bool; 4], width: f64, height: f64,
+                        options: &ScreenOptions, resized: Option<DockPosition>) {
+    use DockPosition::*;
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+
+    for dock in DockPosition::array() {
+        let dimension = if matches!(dock, Top | Bottom) { height } else { width };
+        let size = &mut sizes[dock as usize];
+        *size = size.min(options.max_dock_size * dimension).max(options.min_dock_size);
+    }
+
+    for (a, b, dimension) in [(Top, Bottom, height), (Left, Right, width)] {
+        let size = |dock: DockPosition| if visible[dock as usize] { sizes[dock as usize] } else { 0.0 };
+        let (size_a, size_b) = (size(a), size(b));
+        let available = (dimension - options.min_center_size).max(0.0);
+        let excess = size_a + size_b - available;
+        if excess <= 0.0 {
+            continue;
+        }
+
+        match resized {
+            Some(dock) if dock == a || dock == b => {
+                let size = &mut sizes[dock as usize];
+                *size = (*size - excess).max(0.0);
+            }
+            _ => {
+                let scale = available / (size_a + size_b);
+                for dock in [a, b] {
+                    if visible[dock as usize] {
+                        sizes[dock as usize] *= scale;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Size a dock starts with
+fn default_dock_size(dock: DockPosition, width: f64, height: f64) -> f64 {
+    use DockPosition::*;
+    match dock {
+        Top    => height / 10.0,
+        Left   => width / 10.0,
+        Bottom => height / 5.0,
+        Right  => width / 5.0,
+        Center => unreachable!(),
+    }
+}
+
+/// The screen is positioned absolutely, so its parent has to be positioned
+fn make_containing_block(parent: &HtmlElement) {
+    let position = gloo::utils::window()
+        .get_computed_style(parent)
+        .ok()
+        .flatten()
+        .and_then(|style| style.get_property_value("position").ok());
+    if matches!(position.as_deref(), Some("static") | None) {
+        if let Err(error) = parent.style().set_property("position", "relative") {
+            gloo::console::error!("Couldn't position the screen's parent:", error);
+        }
+    }
+}
+
+/**
+ * Calls a callback whenever an element changes its size
+ *
+ * Like gloo's `EventListener` the observer is disconnected when this is dropped.
+ */
+pub struct ResizeListener {
+    observer: ResizeObserver,
+    _callback: Closure<dyn FnMut()>,
+}
+impl ResizeListener {
+    pub fn new(element: &Element, callback: impl FnMut() + 'static) -> ResizeListener {
+        let callback = Closure::wrap(Box::new(callback) as Box<dyn FnMut()>);
+        let observer = ResizeObserver::new(callback.as_ref().unchecked_ref())
+            .expect("ResizeObserver is supported by all relevant browsers");
+        observer.observe(element);
+        ResizeListener { observer, _callback: callback }
+    }
+}
+impl Drop for ResizeListener {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
 fn find_and_delete<T: PartialEq>(vec: &mut Vec<T>, t : &T) {
     let indexes: Vec<usize> = vec.iter()
         .enumerate()
@@ -383,4 +1790,54 @@ fn find_and_delete<T: PartialEq>(vec: &mut Vec<T>, t : &T) {
     for index in indexes.into_iter().rev() {
         vec.remove(index);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DockPosition::*;
+
+    const ALL_VISIBLE: [bool; 4] = [true; 4];
+
+    fn options(min_dock_size: f64, max_dock_size: f64, min_center_size: f64) -> ScreenOptions {
+        ScreenOptions { min_dock_size, max_dock_size, min_center_size, ..Default::default() }
+    }
+
+    #[test]
+    fn constrain_min_max() {
+        let mut sizes = [10.0, 900.0, 100.0, 100.0];
+        constrain_dock_sizes(&mut sizes, ALL_VISIBLE, 1000.0, 500.0, &options(50.0, 0.5, 0.0), None);
+        assert_eq!(sizes, [50.0, 500.0, 100.0, 100.0]);
+    }
+
+    #[test]
+    fn constrain_keeps_center() {
+        let mut sizes = [200.0, 300.0, 200.0, 300.0];
+        constrain_dock_sizes(&mut sizes, ALL_VISIBLE, 1000.0, 500.0, &options(0.0, 1.0, 200.0), None);
+        // Both docks give up space in proportion to their sizes
+        assert_eq!(sizes, [150.0, 300.0, 150.0, 300.0]);
+    }
+
+    #[test]
+    fn constrain_resized_dock_gives_way() {
+        let mut sizes = [100.0, 300.0, 100.0, 600.0];
+        constrain_dock_sizes(&mut sizes, ALL_VISIBLE, 1000.0, 500.0, &options(0.0, 1.0, 200.0), Some(Right));
+        assert_eq!(sizes, [100.0, 300.0, 100.0, 500.0]);
+    }
+
+    #[test]
+    fn constrain_ignores_hidden_docks() {
+        let mut sizes = [100.0, 600.0, 100.0, 600.0];
+        let visible = [true, true, true, false];
+        constrain_dock_sizes(&mut sizes, visible, 1000.0, 500.0, &options(0.0, 1.0, 200.0), None);
+        assert_eq!(sizes, [100.0, 600.0, 100.0, 600.0]);
+    }
+
+    #[test]
+    fn constrain_without_space() {
+        let mut sizes = [50.0, 100.0, 100.0, 200.0];
+        for (width, height) in [(0.0, 0.0), (1000.0, 0.0), (0.0, 500.0)] {
+            constrain_dock_sizes(&mut sizes, ALL_VISIBLE, width, height, &ScreenOptions::default(), None);
+            assert_eq!(sizes, [50.0, 100.0, 100.0, 200.0]);
+        }
+    }
+}
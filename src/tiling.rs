@@ -0,0 +1,390 @@
+use crate::screen::DockPosition;
+
+/**
+ * Recursive layout of the center dock
+ *
+ * Every node carries an id which stays stable while the tree is restructured,
+ * so callbacks created during rendering can safely refer to them.
+ */
+pub struct TileTree {
+    pub root: Tile,
+    next_id: usize,
+}
+pub enum Tile {
+    Leaf {
+        id: usize,
+        windows: Vec<usize>,
+    },
+    Split {
+        id: usize,
+        direction: SplitDirection,
+        /// Share of the first child in the range `MIN_RATIO..=MAX_RATIO`
+        ratio: f64,
+        first: Box<Tile>,
+        second: Box<Tile>,
+    },
+}
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SplitDirection {
+    /// Children are placed next to each other
+    Horizontal,
+    /// Children are placed on top of each other
+    Vertical,
+}
+
+const MIN_RATIO: f64 = 0.05;
+const MAX_RATIO: f64 = 0.95;
+
+impl Default for TileTree {
+    fn default() -> Self {
+        TileTree {
+            root: Tile::Leaf { id: 0, windows: Vec::new() },
+            next_id: 1,
+        }
+    }
+}
+impl TileTree {
+    /// Add a window to the first leaf
+    pub fn insert(&mut self, window: usize) {
+        let mut tile = &mut self.root;
+        loop {
+            match tile {
+                Tile::Leaf { windows, .. } => {
+                    windows.push(window);
+                    return;
+                }
+                Tile::Split { first, .. } => tile = first,
+            }
+        }
+    }
+
+    /// Add a window to a leaf or split the leaf and place the window at one of its edges
+    ///
+    /// Empty leaves are never split, the window just fills them.
+    /// Returns `false` if no leaf with this id exists.
+    pub fn insert_at(&mut self, leaf: usize, edge: DockPosition, window: usize) -> bool {
+        let new_id = self.next_id;
+        let tile = match self.root.find_mut(leaf) {
+            Some(tile @ Tile::Leaf { .. }) => tile,
+            _ => return false,
+        };
+
+        if let Tile::Leaf { windows, .. } = tile {
+            if edge == DockPosition::Center || windows.is_empty() {
+                windows.push(window);
+                return true;
+            }
+        }
+
+        use DockPosition::*;
+        let direction = match edge {
+            Left | Right => SplitDirection::Horizontal,
+            Top | Bottom => SplitDirection::Vertical,
+            Center => unreachable!(),
+        };
+        let new = Tile::Leaf { id: new_id, windows: vec![window] };
+        let old = std::mem::replace(tile, Tile::Leaf { id: 0, windows: Vec::new() });
+        let (first, second) = match edge {
+            Left | Top => (new, old),
+            _          => (old, new),
+        };
+        *tile = Tile::Split {
+            id: new_id + 1,
+            direction,
+            ratio: 0.5,
+            first: Box::new(first),
+            second: Box::new(second),
+        };
+        self.next_id += 2;
+        true
+    }
+
+    /// Remove a window and collapse any split which is left with an empty leaf
+    pub fn remove(&mut self, window: usize) {
+        self.root.remove(window);
+    }
+
+    /// Move the divider of a split by some pixels
+    ///
+    /// `width` and `height` are the size of the whole tree in pixels.
+    pub fn resize(&mut self, split: usize, dx: i32, dy: i32, width: f64, height: f64) -> bool {
+        self.root.resize(split, dx as f64, dy as f64, width, height)
+    }
+
+    /// Get the share of a split's first child
+    pub fn ratio(&self, split: usize) -> Option<f64> {
+        match self.root.find(split)? {
+            Tile::Split { ratio, .. } => Some(*ratio),
+            Tile::Leaf { .. } => None,
+        }
+    }
+
+    /// Get the window displayed in a leaf
+    pub fn visible(windows: &[usize]) -> Option<usize> {
+        windows.last().copied()
+    }
+
+    /// Display a window in its leaf by moving it in front of the others
+    ///
+    /// Returns `false` if the window isn't in the tree.
+    pub fn raise(&mut self, window: usize) -> bool {
+        self.root.raise(window)
+    }
+
+    /// Check whether a window is the one displayed in its leaf
+    pub fn is_visible(&self, window: usize) -> bool {
+        self.root.is_visible(window)
+    }
+}
+
+impl Tile {
+    pub fn id(&self) -> usize {
+        match self {
+            Tile::Leaf { id, .. } => *id,
+            Tile::Split { id, .. } => *id,
+        }
+    }
+
+    fn find(&self, needle: usize) -> Option<&Tile> {
+        if self.id() == needle {
+            return Some(self);
+        }
+        match self {
+            Tile::Leaf { .. } => None,
+            Tile::Split { first, second, .. } => first.find(needle).or_else(|| second.find(needle)),
+        }
+    }
+
+    fn find_mut(&mut self, needle: usize) -> Option<&mut Tile> {
+        if self.id() == needle {
+            return Some(self);
+        }
+        match self {
+            Tile::Leaf { .. } => None,
+            Tile::Split { first, second, .. } => {
+                match first.find_mut(needle) {
+                    Some(tile) => Some(tile),
+                    None => second.find_mut(needle),
+                }
+            }
+        }
+    }
+
+    fn raise(&mut self, window: usize) -> bool {
+        match self {
+            Tile::Leaf { windows, .. } => match windows.iter().position(|&id| id == window) {
+                Some(index) => {
+                    let id = windows.remove(index);
+                    windows.push(id);
+                    true
+                }
+                None => false,
+            },
+            Tile::Split { first, second, .. } => first.raise(window) || second.raise(window),
+        }
+    }
+
+    fn is_visible(&self, window: usize) -> bool {
+        match self {
+            Tile::Leaf { windows, .. } => TileTree::visible(windows) == Some(window),
+            Tile::Split { first, second, .. } => first.is_visible(window) || second.is_visible(window),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Tile::Leaf { windows, .. } if windows.is_empty())
+    }
+
+    fn remove(&mut self, window: usize) {
+        match self {
+            Tile::Leaf { windows, .. } => windows.retain(|&id| id != window),
+            Tile::Split { first, second, .. } => {
+                first.remove(window);
+                second.remove(window);
+
+                // Replace this split with its remaining child
+                let remaining = if first.is_empty() {
+                    Some(std::mem::replace(second.as_mut(), Tile::Leaf { id: 0, windows: Vec::new() }))
+                } else if second.is_empty() {
+                    Some(std::mem::replace(first.as_mut(), Tile::Leaf { id: 0, windows: Vec::new() }))
+                } else {
+                    None
+                };
+                if let Some(remaining) = remaining {
+                    *self = remaining;
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, needle: usize, dx: f64, dy: f64, width: f64, height: f64) -> bool {
+        match self {
+            Tile::Leaf { .. } => false,
+            Tile::Split { id, direction, ratio, first, second } => {
+                let (size, delta) = match direction {
+                    SplitDirection::Horizontal => (width, dx),
+                    SplitDirection::Vertical => (height, dy),
+                };
+                if *id == needle {
+                    if size > 0.0 {
+                        *ratio = (*ratio + delta / size).clamp(MIN_RATIO, MAX_RATIO);
+                    }
+                    return true;
+                }
+
+                let (first_size, second_size) = match direction {
+                    SplitDirection::Horizontal => ((width * *ratio, height), (width * (1.0 - *ratio), height)),
+                    SplitDirection::Vertical => ((width, height * *ratio), (width, height * (1.0 - *ratio))),
+                };
+                first.resize(needle, dx, dy, first_size.0, first_size.1)
+                    || second.resize(needle, dx, dy, second_size.0, second_size.1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows(tile: &Tile) -> &[usize] {
+        match tile {
+            Tile::Leaf { windows, .. } => windows,
+            Tile::Split { .. } => panic!("Expected a leaf"),
+        }
+    }
+
+    fn split(tile: &Tile) -> (usize, SplitDirection, f64, &Tile, &Tile) {
+        match tile {
+            Tile::Split { id, direction, ratio, first, second } => (*id, *direction, *ratio, first, second),
+            Tile::Leaf { .. } => panic!("Expected a split"),
+        }
+    }
+
+    #[test]
+    fn insert_at_center_stacks() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        assert!(tree.insert_at(0, DockPosition::Center, 2));
+        assert_eq!(windows(&tree.root), &[1, 2]);
+        assert_eq!(TileTree::visible(windows(&tree.root)), Some(2));
+    }
+
+    #[test]
+    fn insert_at_edge_splits() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        assert!(tree.insert_at(0, DockPosition::Left, 2));
+        let (_, direction, ratio, first, second) = split(&tree.root);
+        assert_eq!(direction, SplitDirection::Horizontal);
+        assert_eq!(ratio, 0.5);
+        assert_eq!(windows(first), &[2]);
+        assert_eq!(windows(second), &[1]);
+
+        // The old leaf keeps its id
+        assert!(tree.insert_at(0, DockPosition::Bottom, 3));
+        let (_, _, _, _, second) = split(&tree.root);
+        let (_, direction, _, first, second) = split(second);
+        assert_eq!(direction, SplitDirection::Vertical);
+        assert_eq!(windows(first), &[1]);
+        assert_eq!(windows(second), &[3]);
+    }
+
+    #[test]
+    fn insert_at_empty_leaf_fills_it() {
+        let mut tree = TileTree::default();
+        assert!(tree.insert_at(0, DockPosition::Right, 1));
+        assert_eq!(windows(&tree.root), &[1]);
+    }
+
+    #[test]
+    fn insert_at_unknown_leaf() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        assert!(!tree.insert_at(42, DockPosition::Center, 2));
+        tree.insert_at(0, DockPosition::Top, 2);
+        let split_id = tree.root.id();
+        assert!(!tree.insert_at(split_id, DockPosition::Center, 3));
+    }
+
+    #[test]
+    fn only_last_window_is_visible() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        tree.insert_at(0, DockPosition::Center, 2);
+        tree.insert_at(0, DockPosition::Right, 3);
+        assert!(!tree.is_visible(1));
+        assert!(tree.is_visible(2));
+        assert!(tree.is_visible(3));
+        assert!(!tree.is_visible(4));
+    }
+
+    #[test]
+    fn raise_shows_window() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        tree.insert_at(0, DockPosition::Center, 2);
+        tree.insert_at(0, DockPosition::Center, 3);
+        assert!(tree.raise(1));
+        assert_eq!(windows(&tree.root), &[2, 3, 1]);
+        assert!(tree.is_visible(1));
+        assert!(!tree.raise(4));
+    }
+
+    #[test]
+    fn remove_collapses_split() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        tree.insert_at(0, DockPosition::Right, 2);
+        tree.insert_at(0, DockPosition::Bottom, 3);
+
+        tree.remove(3);
+        let (_, _, _, first, second) = split(&tree.root);
+        assert_eq!(windows(first), &[1]);
+        assert_eq!(windows(second), &[2]);
+
+        tree.remove(1);
+        assert_eq!(windows(&tree.root), &[2]);
+
+        tree.remove(2);
+        assert!(windows(&tree.root).is_empty());
+    }
+
+    #[test]
+    fn resize_clamps() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        tree.insert_at(0, DockPosition::Right, 2);
+        let (id, ..) = split(&tree.root);
+
+        assert!(tree.resize(id, 100, 0, 1000.0, 500.0));
+        assert!((split(&tree.root).2 - 0.6).abs() < 1e-9);
+
+        // Vertical movement doesn't affect a horizontal split
+        assert!(tree.resize(id, 0, 100, 1000.0, 500.0));
+        assert!((split(&tree.root).2 - 0.6).abs() < 1e-9);
+
+        tree.resize(id, 5000, 0, 1000.0, 500.0);
+        assert_eq!(split(&tree.root).2, MAX_RATIO);
+        tree.resize(id, -5000, 0, 1000.0, 500.0);
+        assert_eq!(tree.ratio(id), Some(MIN_RATIO));
+
+        assert!(!tree.resize(42, 10, 10, 1000.0, 500.0));
+        assert_eq!(tree.ratio(42), None);
+    }
+
+    #[test]
+    fn resize_nested_uses_child_size() {
+        let mut tree = TileTree::default();
+        tree.insert(1);
+        tree.insert_at(0, DockPosition::Right, 2);
+        tree.insert_at(0, DockPosition::Left, 3);
+        let (_, _, _, first, _) = split(&tree.root);
+        let (inner, ..) = split(first);
+
+        // The inner split is half as wide as the tree
+        tree.resize(inner, 50, 0, 1000.0, 500.0);
+        let (_, _, _, first, _) = split(&tree.root);
+        assert!((split(first).2 - 0.6).abs() < 1e-9);
+    }
+}
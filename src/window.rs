@@ -1,8 +1,13 @@
 use serde::Deserialize;
 use wasm_bindgen::prelude::*;
-use web_sys::Element;
-use gloo::utils::document;
+use web_sys::{Element, Event};
+use gloo::events::EventListener;
+use gloo::utils::{document, window};
+use gloo::utils::format::JsValueSerdeExt;
+use js_sys::{Function, Reflect};
 use crate::screen::DockPosition;
+use crate::drop_zone::Accepts;
+use crate::payload::PayloadKind;
 
 /**
  * This struct directly matches the javascript object expected `Screen.newWindow`.
@@ -16,11 +21,30 @@ pub struct WindowInit {
     pub title: Option<String>,
     pub icon: Option<String>,
     pub dock: DockPosition,
+    /// Mime types like `"Files"` or `"text/uri-list"` of drops the window takes
+    #[serde(default)]
+    pub accepts: Vec<String>,
+    /// Function receiving the data of accepted drops and the window's `<div>`
+    ///
+    /// Json can't carry functions, so it is read separately as `onDrop`.
+    #[serde(skip)]
+    pub on_drop: Option<Function>,
 }
 impl TryFrom<JsValue> for WindowInit {
     type Error = serde_json::Error;
     fn try_from(value: JsValue) -> Result<Self, Self::Error> {
-        value.into_serde()
+        let on_drop = Reflect::get(&value, &JsValue::from_str("onDrop")).ok()
+            .and_then(|on_drop| on_drop.dyn_into().ok());
+        let mut init: WindowInit = value.into_serde()?;
+        for mime in init.accepts.iter() {
+            match PayloadKind::from_mime(mime) {
+                None => return Err(serde::de::Error::custom(format!("Unknown drop type: {}", mime))),
+                Some(kind) if kind.is_window() => return Err(serde::de::Error::custom("Windows are only dropped onto docks")),
+                Some(_) => {},
+            }
+        }
+        init.on_drop = on_drop;
+        Ok(init)
     }
 }
 
@@ -35,6 +59,13 @@ pub struct Window {
     pub div: Element,
     pub current_dock: Option<DockPosition>,
     pub last_dock: DockPosition,
+    /// Only rendered as header inside an accordion dock
+    pub collapsed: bool,
+    /// Set while the window is detached into its own browser window
+    pub popup: Option<Popup>,
+    /// Payloads the window takes, `None` if it doesn't take any
+    pub accepts: Option<Accepts>,
+    pub on_drop: Option<Function>,
 }
 impl From<WindowInit> for Window {
     fn from(init: WindowInit) -> Self {
@@ -46,6 +77,68 @@ impl From<WindowInit> for Window {
                 .expect("Couldn't create new <div>"),
             current_dock: None,
             last_dock: init.dock,
+            collapsed: false,
+            popup: None,
+            accepts: (init.on_drop.is_some() && !init.accepts.is_empty()).then(|| Accepts::new(init.accepts)),
+            on_drop: init.on_drop,
+        }
+    }
+}
+
+/**
+ * A separate browser window a `Window`'s div has been moved into
+ */
+pub struct Popup {
+    pub window: web_sys::Window,
+    close_listener: EventListener,
+}
+impl Popup {
+    /// Open a new browser window and move the div into it
+    ///
+    /// `on_close` is called when the user closes the browser window.
+    /// Returns `None` if the browser blocked the popup or it couldn't be set up.
+    pub fn open(title: &str, div: &Element, on_close: impl FnMut(&Event) + 'static) -> Option<Popup> {
+        let rect = div.get_bounding_client_rect();
+        let features = format!("popup,width={},height={}",
+            rect.width().max(200.0).floor(), rect.height().max(200.0).floor());
+        let popup = window()
+            .open_with_url_and_target_and_features("", "_blank", &features)
+            .ok()??;
+
+        // Don't leave an empty browser window behind
+        if Popup::fill(&popup, title, div).is_none() {
+            let _ = popup.close();
+            return None;
         }
+
+        let close_listener = EventListener::new(&popup, "pagehide", on_close);
+        Some(Popup { window: popup, close_listener })
+    }
+
+    /// Style a freshly opened browser window like this page and move the div into it
+    fn fill(popup: &web_sys::Window, title: &str, div: &Element) -> Option<()> {
+        let popup_document = popup.document()?;
+        popup_document.set_title(title);
+
+        // Copy the stylesheets to keep the content styled
+        let head = popup_document.head()?;
+        let styles = document().query_selector_all("link[rel=stylesheet], style").ok()?;
+        for i in 0..styles.length() {
+            if let Some(Ok(style)) = styles.get(i).map(|style| style.clone_node_with_deep(true)) {
+                let _ = head.append_child(&style);
+            }
+        }
+
+        let body = popup_document.body()?;
+        body.set_class_name("waw-popup");
+        body.append_child(div).ok()?;
+        Some(())
+    }
+
+    /// Take back the div and close the browser window
+    pub fn close(self, div: &Element) {
+        drop(self.close_listener);
+        div.remove();
+        let _ = self.window.close();
     }
 }